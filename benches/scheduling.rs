@@ -0,0 +1,102 @@
+//! Benchmarks for the scheduler. The cost of handling a message should depend on the number of
+//! actors with work to do, not on the total number of actors in the system.
+//!
+//! These run without the (nightly-only) built-in bench harness, timing a fixed number of
+//! iterations of each benchmark and reporting the average time per iteration.
+extern crate romeo;
+
+use romeo::actor::*;
+use romeo::system::Config;
+use romeo::{Address, System};
+
+use std::time::{Duration, Instant};
+
+/// The number of timed iterations of each benchmark, after as many warm-up iterations
+const ITERATIONS: u32 = 1_000;
+
+struct Counter {
+    count: u64,
+}
+impl Actor for Counter {}
+
+struct CounterProps;
+impl Props for CounterProps {}
+
+impl ActorConstructable<CounterProps> for Counter {
+    fn new(_: &CounterProps) -> Self {
+        Counter { count: 0 }
+    }
+}
+
+impl Receives<u64> for Counter {
+    type Response = u64;
+
+    fn receive(&mut self, n: u64, _ctx: &Context<Self>) -> Result<u64, Failure> {
+        self.count += n;
+        Ok(self.count)
+    }
+}
+
+fn start_system() -> System {
+    let mut system = System::new();
+    system.with_config(Config::default().threads(2));
+    system.spawn();
+    system
+}
+
+/// Run `iteration` repeatedly, printing the average time it took once warmed up
+fn bench<F: FnMut()>(name: &str, mut iteration: F) {
+    for _ in 0..ITERATIONS {
+        iteration();
+    }
+    let started = Instant::now();
+    for _ in 0..ITERATIONS {
+        iteration();
+    }
+    let average = started.elapsed() / ITERATIONS;
+    println!("{:<32} {:>12} ns/iter", name, average.as_nanos());
+}
+
+// ---
+// Idle actors
+// ---
+
+/// Round-trip a message through a single actor, while `idle` other actors never receive any
+fn round_trip_with_idle_actors(name: &str, idle: usize) {
+    let mut system = start_system();
+    let _idle: Vec<Address<Counter>> = (0..idle).map(|_| system.new_actor(CounterProps)).collect();
+    let active: Address<Counter> = system.new_actor(CounterProps);
+
+    bench(name, || {
+        active.ask(1u64).wait_timeout(Duration::from_secs(1)).unwrap();
+    });
+    system.graceful_shutdown().unwrap();
+}
+
+// ---
+// Active actors
+// ---
+
+/// Send a message to each of `active` actors and wait until all of them have been handled
+fn fan_out_to_active_actors(name: &str, active: usize) {
+    let mut system = start_system();
+    let actors: Vec<Address<Counter>> = (0..active).map(|_| system.new_actor(CounterProps)).collect();
+
+    bench(name, || {
+        let replies: Vec<_> = actors.iter().map(|a| a.ask(1u64)).collect();
+        for reply in replies {
+            reply.wait_timeout(Duration::from_secs(1)).unwrap();
+        }
+    });
+    system.graceful_shutdown().unwrap();
+}
+
+fn main() {
+    round_trip_with_idle_actors("round_trip_0_idle_actors", 0);
+    round_trip_with_idle_actors("round_trip_1_000_idle_actors", 1_000);
+    round_trip_with_idle_actors("round_trip_10_000_idle_actors", 10_000);
+
+    fan_out_to_active_actors("fan_out_10_active_actors", 10);
+    fan_out_to_active_actors("fan_out_100_active_actors", 100);
+    fan_out_to_active_actors("fan_out_1_000_active_actors", 1_000);
+}
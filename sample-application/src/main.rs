@@ -29,7 +29,9 @@ impl ActorConstructable<AccountingProps> for AccountingActor {
 }
 
 impl Receives<u8> for AccountingActor {
-    fn receive(&mut self, msg: u8, ctx: &Context) {
+    type Response = ();
+
+    fn receive(&mut self, msg: u8, ctx: &Context<Self>) -> Result<(), Failure> {
         self.balance += msg as i32;
         println!("Received: {}", msg);
         println!("Current balance: {0}", self.balance);
@@ -42,6 +44,7 @@ impl Receives<u8> for AccountingActor {
         } else {
             println!("not stopping actor");
         }
+        Ok(())
     }
 }
 
@@ -57,7 +60,7 @@ fn main() {
     address.send(32u8); 
     address.send(0u8);
 
-    // system.graceful_shutdown();
-    use std::thread;
-    loop { thread::sleep_ms(100); }
+    if let Err(err) = system.graceful_shutdown() {
+        println!("{}", err);
+    }
 }
@@ -1,6 +1,21 @@
+use super::address::Address;
+use super::behavior::Behavior;
+use super::cell::{ACell, Cell, Notify, SystemMessage};
+use super::mailbox::{MailboxConfig, MailboxRetention, Overflow};
+use super::placement::{Alongside, Placement};
+use super::runtime::Runtime;
 use super::scheduler::Scheduler;
+use super::supervision::{Backoff, SupervisorStrategy};
+use super::timer::Timer;
 
-use std::sync::{Weak};
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 
 use uuid::Uuid;
 
@@ -10,6 +25,85 @@ use uuid::Uuid;
 pub trait Actor: Send + Sync {
     fn start(&mut self) {}
     fn pre_stop(&mut self) {}
+
+    /// Called once the actor has stopped for good: its children have stopped, and its watchers
+    /// have been notified. Unlike `pre_stop`, this is not called when the actor is restarted.
+    fn post_stop(&mut self) {}
+
+    /// Called on the actor being replaced when it is restarted, along with why it is restarted
+    /// and (if the actor failed) the type of the message it failed to handle. By default this
+    /// calls `pre_stop`.
+    fn pre_restart(&mut self, _reason: &RestartReason, _last_message: Option<&'static str>) {
+        self.pre_stop();
+    }
+
+    /// Called on the fresh actor replacing a restarted one, along with why the actor was
+    /// restarted. By default this calls `start`.
+    fn post_restart(&mut self, _reason: &RestartReason) {
+        self.start();
+    }
+
+    /// Decide what should happen to this actor when one of its handlers returns an error. By
+    /// default a failing actor is restarted (see `Directive`).
+    fn on_failure(&self, _failure: &Failure) -> Directive {
+        Directive::Restart
+    }
+
+    /// The strategy used to supervise the children of this actor. This is read each time the
+    /// actor is started. See `romeo::supervision::SupervisorStrategy`.
+    fn supervisor_strategy(&self) -> SupervisorStrategy {
+        SupervisorStrategy::default()
+    }
+}
+
+// ---
+// Failure Handling
+// ---
+
+/// The error type returned from a failed message handler. Any error type (or a plain string)
+/// can be converted into a `Failure`, so the `?` operator works as expected within handlers.
+pub type Failure = Box<dyn Error + Send + Sync>;
+
+/// The failure of an actor whose handler panicked. Panics are caught by the actor's scheduler, so
+/// that a panicking actor is restarted or stopped (see `Actor::on_failure`) like any other
+/// failing actor, without harming the other actors on its thread.
+#[derive(Debug)]
+pub struct Panicked {
+    /// The message the handler panicked with
+    pub message: String,
+}
+impl Display for Panicked {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "handler panicked: {}", self.message)
+    }
+}
+impl Error for Panicked {}
+
+/// Why an actor is restarted, see `Actor::pre_restart` and `Actor::post_restart`
+#[derive(Clone, Debug)]
+pub enum RestartReason {
+    /// The actor itself failed (or failed on behalf of a child, see `Directive::Escalate`)
+    Failed(Arc<Failure>),
+    /// The actor was restarted along with a failed sibling, by the `SupervisorStrategy` of
+    /// their parent. The id and failure of the sibling are given.
+    SiblingFailed(Uuid, Arc<Failure>),
+    /// The actor asked to be restarted, see `Context::restart`
+    Requested,
+}
+
+/// Directive is the decision made about an actor after a handler has failed. It is chosen by
+/// `Actor::on_failure` and carried out by the scheduler the actor lives on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Directive {
+    /// Ignore the failure and keep processing messages with the current actor state
+    Resume,
+    /// Replace the actor with a fresh instance (see `Context::restart`)
+    Restart,
+    /// Stop the actor permanently (see `Context::stop`)
+    Stop,
+    /// Hand the failure up to the supervisor of the actor, failing the supervisor itself.
+    /// Actors without a supervisor are stopped.
+    Escalate,
 }
 
 // ---
@@ -20,15 +114,146 @@ pub trait ActorConstructable<P: Props>: Actor {
     fn new(props: &P) -> Self;
 }
 
+/// ActorConfig is the configuration for a single actor and can be specified when creating the
+/// actor with `System::new_actor_with` or `Context::spawn_with`. Actors created without one use
+/// the default.
+///
+/// Values
+/// ------
+/// + `mailbox` - The capacity of the actor's mailbox and the `Overflow` policy to apply when it
+///               is full. Defaults to an unbounded mailbox. See `ActorConfig::bounded_mailbox`.
+/// + `placement` - The strategy used to choose the actor's scheduler, overriding the system-wide
+///                 strategy (see `Config::placement`). Defaults to the system-wide strategy.
+/// + `placement_key` - A key for placement strategies that group actors by key, such as
+///                     `romeo::placement::HashOfKey`. Defaults to no key.
+/// + `dispatcher` - The name of the dispatcher (pool of threads) the actor runs on, as declared
+///                  with `Config::dispatcher`. Defaults to the default dispatcher.
+/// + `stash_capacity` - The most messages the actor may stash (see `Context::stash`). Defaults
+///                      to no limit.
+/// + `restart_backoff` - How long to hold back restarts after the actor fails, see
+///                       `romeo::supervision::Backoff`. Defaults to restarting immediately.
+/// + `mailbox_on_restart` - Whether the messages waiting for the actor are kept or discarded
+///                          when it restarts, see `romeo::mailbox::MailboxRetention`. Defaults
+///                          to keeping them.
+#[derive(Clone)]
+pub struct ActorConfig {
+    pub(crate) mailbox: MailboxConfig,
+    pub(crate) stash_capacity: usize,
+    pub(crate) restart_backoff: Option<Backoff>,
+    pub(crate) mailbox_retention: MailboxRetention,
+    pub(crate) dispatcher: Option<String>,
+    pub(crate) placement: Option<Arc<dyn Placement>>,
+    pub(crate) placement_key: Option<u64>,
+}
+
+impl ActorConfig {
+    /// Run the actor on the named dispatcher (see `Config::dispatcher`), such as a pool set aside
+    /// for actors that block. Creating the actor panics if there is no such dispatcher.
+    pub fn dispatcher(mut self, name: &str) -> Self {
+        self.dispatcher = Some(name.to_string());
+        self
+    }
+
+    /// Choose the actor's scheduler with the given strategy, rather than the system-wide one.
+    pub fn placement<P: Placement + 'static>(mut self, placement: P) -> Self {
+        self.placement = Some(Arc::new(placement));
+        self
+    }
+
+    /// Place the actor on the same scheduler as another actor, so that a chatty pair of actors
+    /// can share a thread. Note that either actor may later be stolen by an idle scheduler.
+    pub fn colocate_with<B: Actor + 'static>(mut self, other: &Address<B>) -> Self {
+        let cell: Option<Weak<dyn ACell>> = other.cell().map(|cell| Arc::downgrade(&cell) as Weak<dyn ACell>);
+        self.placement = Some(Arc::new(Alongside { cell }));
+        self
+    }
+
+    pub fn placement_key<K: Hash>(mut self, key: &K) -> Self {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.placement_key = Some(hasher.finish());
+        self
+    }
+
+    /// Limit the actor's mailbox to `capacity` messages, applying `overflow` to messages sent
+    /// while the mailbox is full.
+    pub fn bounded_mailbox(mut self, capacity: usize, overflow: Overflow) -> Self {
+        self.mailbox = MailboxConfig::bounded(capacity, overflow);
+        self
+    }
+
+    pub fn stash_capacity(mut self, capacity: usize) -> Self {
+        self.stash_capacity = capacity;
+        self
+    }
+
+    /// Hold back restarts of the actor after it fails, backing off between repeated failures.
+    pub fn restart_backoff(mut self, backoff: Backoff) -> Self {
+        self.restart_backoff = Some(backoff);
+        self
+    }
+
+    /// Keep or discard the messages waiting for the actor when it restarts.
+    pub fn mailbox_on_restart(mut self, retention: MailboxRetention) -> Self {
+        self.mailbox_retention = retention;
+        self
+    }
+}
+
+impl Default for ActorConfig {
+    fn default() -> Self {
+        ActorConfig {
+            mailbox: MailboxConfig::unbounded(),
+            stash_capacity: usize::MAX,
+            restart_backoff: None,
+            mailbox_retention: MailboxRetention::Keep,
+            dispatcher: None,
+            placement: None,
+            placement_key: None,
+        }
+    }
+}
+
 // ---
 // Message Handling
 // ---
 pub trait Receives<M>
 where
-    Self: Actor,
+    Self: Actor + Sized,
 {
-    // TODO: should return something, because need to know about failures
-    fn receive(&mut self, msg: M, ctx: &Context);
+    /// The value produced by handling a message. This is what is handed back to the caller
+    /// of `Address::ask`, and is simply discarded for messages delivered via `Address::send`.
+    type Response;
+
+    /// Handle a message. Returning an error marks the actor as failed, after which the
+    /// `Directive` from `Actor::on_failure` is applied.
+    fn receive(&mut self, msg: M, ctx: &Context<Self>) -> Result<Self::Response, Failure>;
+}
+
+/// The future returned from an async handler, see `ReceivesAsync`. The future has to be `Send`,
+/// as it moves along with its actor when the actor is stolen by another scheduler.
+pub type Handling<R> = Pin<Box<dyn Future<Output = Result<R, Failure>> + Send>>;
+
+/// ReceivesAsync is the async counterpart of `Receives`, for handlers that have to wait (on I/O,
+/// for example) without blocking a scheduler thread. Messages for an async handler are sent with
+/// `Address::send_async` or `Address::ask_async`.
+///
+/// The returned future is driven by the actor's scheduler, and the actor handles no further
+/// messages until it has completed, so that messages are still handled one at a time and in
+/// order. Stopping or restarting the actor drops the future.
+///
+/// As the future cannot borrow the actor, any state it produces has to be sent back to the actor
+/// as a message of its own.
+pub trait ReceivesAsync<M>
+where
+    Self: Actor + Sized,
+{
+    /// The value produced by the future, handed back to the caller of `Address::ask_async`
+    type Response;
+
+    /// Handle a message. The future resolving to an error marks the actor as failed, just as a
+    /// failing `Receives::receive` does.
+    fn receive_async(&mut self, msg: M, ctx: &Context<Self>) -> Handling<Self::Response>;
 }
 
 
@@ -41,41 +266,254 @@ where
 /// A context does not have any state that persists between calls, so it doesn't expose any
 /// methods for storing state, and should not be sought after as an alternative to defining
 /// state within an actor.
-pub struct Context {
+pub struct Context<A: Actor> {
     parent_cell_uuid: Uuid,
     running_state: State,
     /// In order to execute on some of the responsibilities of the context (such as shutting
     /// down), a weak reference to the scheduler must be maintained
     parent_scheduler: Weak<Scheduler>,
+    /// The cell of the actor this context belongs to, which becomes the supervisor of any
+    /// actors created through the context
+    parent_cell: Weak<Cell<A>>,
 }
-impl Context {
-    pub(crate) fn new(uuid: Uuid, state: State, scheduler: Weak<Scheduler>) -> Self {
+impl<A: Actor + 'static> Context<A> {
+    pub(crate) fn new(uuid: Uuid, state: State, scheduler: Weak<Scheduler>, cell: Weak<Cell<A>>) -> Self {
         Context {
             parent_cell_uuid: uuid,
             running_state: state,
             parent_scheduler: scheduler,
+            parent_cell: cell,
         }
     }
+    /// Stop the actor. The actor finishes handling the current message, but no further messages
+    /// are handled once its children (if any) have been stopped.
     pub fn stop(&self) {
-        let scheduler = Weak::upgrade(&self.parent_scheduler);
-        if scheduler.is_none() {
+        self.tell(SystemMessage::Stop);
+    }
+
+    /// Restart the actor, replacing it with a freshly constructed one before the next message.
+    pub fn restart(&self) {
+        self.tell(SystemMessage::Restart(RestartReason::Requested));
+    }
+
+    fn tell(&self, msg: SystemMessage) {
+        self.cell().tell(msg);
+    }
+
+    pub(crate) fn cell(&self) -> Arc<Cell<A>> {
+        let cell = Weak::upgrade(&self.parent_cell);
+        if cell.is_none() {
             panic!("Actor orphaned by scheduler!")
         }
-
-        scheduler.unwrap().stop_actor(self.parent_cell_uuid);
+        cell.unwrap()
     }
 
-    pub fn restart(&self) {
-        let scheduler = Weak::upgrade(&self.parent_scheduler);
-        if scheduler.is_none() {
+    fn runtime(&self) -> Arc<Runtime> {
+        let runtime = Weak::upgrade(&self.parent_scheduler).and_then(|s| s.runtime());
+        if runtime.is_none() {
             panic!("Actor orphaned by scheduler!")
         }
+        runtime.unwrap()
+    }
 
-        scheduler.unwrap().restart_actor(self.parent_cell_uuid);
+    /// Send a message to this actor once `delay` has passed. See `schedule_once_to`.
+    pub fn schedule_once<M>(&self, delay: Duration, msg: M) -> Timer
+    where
+        A: Receives<M>,
+        M: Send + 'static,
+    {
+        let myself = Cell::address(self.cell());
+        self.schedule_once_to(delay, &myself, msg)
+    }
+
+    /// Send a message to another actor once `delay` has passed. The returned `Timer` can be
+    /// used to cancel the message, which also happens automatically when this actor stops or
+    /// restarts.
+    ///
+    /// Timers never block on a full mailbox, not even with `Overflow::Block`, as that would hold
+    /// up every timer of the system. A message that does not fit is published as a dead letter.
+    pub fn schedule_once_to<B, M>(&self, delay: Duration, target: &Address<B>, msg: M) -> Timer
+    where
+        B: Actor + Receives<M> + 'static,
+        M: Send + 'static,
+    {
+        let target = target.clone();
+        let mut msg = Some(msg);
+        self.schedule(delay, None, Box::new(move || {
+            if let Some(msg) = msg.take() {
+                target.send_without_blocking(msg);
+            }
+        }))
+    }
+
+    /// Send a message to this actor every `interval`, starting once `interval` has passed. See
+    /// `schedule_repeating_to`.
+    pub fn schedule_repeating<M>(&self, interval: Duration, msg: M) -> Timer
+    where
+        A: Receives<M>,
+        M: Clone + Send + 'static,
+    {
+        let myself = Cell::address(self.cell());
+        self.schedule_repeating_to(interval, &myself, msg)
+    }
+
+    /// Send a (copy of a) message to another actor every `interval`, starting once `interval`
+    /// has passed. The messages are sent at a fixed rate until the returned `Timer` is cancelled,
+    /// which also happens automatically when this actor stops or restarts. Like
+    /// `schedule_once_to`, this never blocks on a full mailbox.
+    ///
+    /// Panics if `interval` is zero.
+    pub fn schedule_repeating_to<B, M>(&self, interval: Duration, target: &Address<B>, msg: M) -> Timer
+    where
+        B: Actor + Receives<M> + 'static,
+        M: Clone + Send + 'static,
+    {
+        if interval.is_zero() {
+            panic!("Repeating timers must have an interval of more than 0");
+        }
+        let target = target.clone();
+        self.schedule(interval, Some(interval), Box::new(move || target.send_without_blocking(msg.clone())))
+    }
+
+    fn schedule(&self, delay: Duration, interval: Option<Duration>, fire: Box<dyn FnMut() + Send>) -> Timer {
+        let timer = self.runtime().timers().schedule(delay, interval, fire);
+        self.cell().add_timer(timer.clone());
+        timer
+    }
+
+    /// Create a new actor as a child of this actor and in return get an address to talk to it.
+    /// The child is supervised by this actor (see `Actor::supervisor_strategy`) and is stopped
+    /// before this actor whenever this actor stops.
+    pub fn spawn<B, P>(&self, props: P) -> Address<B>
+    where
+        B: Actor + ActorConstructable<P> + 'static,
+        P: Props + 'static,
+    {
+        self.spawn_with(props, ActorConfig::default())
+    }
+
+    /// Create a new child actor (see `spawn`) with the given actor configuration.
+    pub fn spawn_with<B, P>(&self, props: P, config: ActorConfig) -> Address<B>
+    where
+        B: Actor + ActorConstructable<P> + 'static,
+        P: Props + 'static,
+    {
+        let supervisor: Weak<dyn ACell> = self.parent_cell.clone();
+        self.runtime().new_actor(props, config, Some(supervisor))
+    }
+
+    /// Switch to a different behavior, replacing the current behavior (if any) from the next
+    /// message onwards. See `romeo::behavior::Behavior`.
+    ///
+    /// This is what other actor frameworks call `become`, which is a reserved word in Rust.
+    pub fn become_behavior(&self, behavior: Behavior<A>) {
+        self.cell().become_behavior(behavior, false);
+    }
+
+    /// Switch to a different behavior from the next message onwards, keeping the current
+    /// behavior to return to with `unbecome`.
+    pub fn push_behavior(&self, behavior: Behavior<A>) {
+        self.cell().become_behavior(behavior, true);
+    }
+
+    /// Return to the previous behavior, or to the actor's `Receives` impls if there is no
+    /// previous behavior.
+    pub fn unbecome(&self) {
+        self.cell().unbecome();
+    }
+
+    /// Set the message being handled aside, to be handled again once `unstash_all` is called, such
+    /// as while the actor is waiting on a dependency. Stashed messages keep their order, and a
+    /// stashed `Address::ask` is replied to once the message is handled after all (the value
+    /// returned by the handler that stashed it is discarded).
+    ///
+    /// If the stash is full (see `ActorConfig::stash_capacity`) the message is handed back inside
+    /// a `StashFull` error, which fails the actor when returned from the handler with `?`. Stashed
+    /// messages are handled by the fresh actor after a restart, and become dead letters once the
+    /// actor stops.
+    ///
+    /// Panics when given anything other than the message being handled.
+    pub fn stash<M: 'static>(&self, msg: M) -> Result<(), StashFull<M>> {
+        self.cell().stash(msg)
+    }
+
+    /// Put all stashed messages back at the front of the mailbox, so that they are handled (in the
+    /// order they were stashed) before any other message.
+    pub fn unstash_all(&self) {
+        self.cell().unstash_all();
+    }
+
+    /// Ask for the whole actor system to be shut down gracefully. This is how a system that runs
+    /// on the caller's thread (see `System::run`) is brought to an end from within.
+    pub fn shutdown_system(&self) {
+        self.runtime().request_shutdown();
+    }
+
+    /// Watch another actor, and receive a `Terminated` message once it has stopped. If the
+    /// actor has already stopped, the `Terminated` message is sent right away.
+    ///
+    /// Like timers, the `Terminated` message never blocks on a full mailbox, not even with
+    /// `Overflow::Block`, as that would hold up the scheduler of the stopped actor. A message
+    /// that does not fit is published as a dead letter.
+    pub fn watch<B: Actor + 'static>(&self, other: &Address<B>)
+    where
+        A: Receives<Terminated>,
+    {
+        let cell = self.cell();
+        let myself = Cell::address(cell.clone());
+        let notify: Notify = Box::new(move |terminated| myself.notify_terminated(terminated));
+        let watched = other.cell();
+        let weak: Weak<dyn ACell> = match watched {
+            Some(ref watched) => Arc::downgrade(watched) as Weak<dyn ACell>,
+            None => Weak::<Cell<B>>::new(),
+        };
+        cell.add_watching(other.id(), weak);
+        match watched {
+            Some(watched) => watched.tell(SystemMessage::Watch(self.parent_cell_uuid, notify)),
+            None => notify(Terminated { id: other.id() }),
+        }
+    }
+
+    /// Stop watching another actor. No `Terminated` message will be handled for the actor after
+    /// this call (even if it was already on its way), unless the actor is watched again.
+    pub fn unwatch<B: Actor + 'static>(&self, other: &Address<B>) {
+        if let Some(watched) = other.cell() {
+            watched.tell(SystemMessage::Unwatch(self.parent_cell_uuid));
+        }
+        if let Some(cell) = Weak::upgrade(&self.parent_cell) {
+            cell.remove_watching(other.id());
+        }
     }
 }
 
-#[derive(Clone)]
+/// The error returned from `Context::stash` when the stash is full, which hands back the message.
+pub struct StashFull<M>(pub M);
+impl<M> StashFull<M> {
+    /// Take back the message that could not be stashed
+    pub fn into_inner(self) -> M {
+        self.0
+    }
+}
+impl<M> Debug for StashFull<M> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "StashFull(..)")
+    }
+}
+impl<M> Display for StashFull<M> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "actor's stash is full")
+    }
+}
+impl<M> Error for StashFull<M> {}
+
+/// The message sent to a watching actor when the actor it watches has stopped (see
+/// `Context::watch`). The `id` is that of the stopped actor, as given by `Address::id`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Terminated {
+    pub id: Uuid,
+}
+
+#[derive(Clone, PartialEq)]
 pub(crate) enum State {
     Halted,
     Starting,
@@ -1,43 +1,323 @@
-use super::actor::{Actor, Receives};
-use super::cell::Cell;
+use super::actor::{Actor, Context, Failure, Receives, ReceivesAsync, Terminated};
+use super::behavior;
+use super::cell::{self, ACell, Cell, Envelope, Rewrap};
+use super::dead_letters::{DeadLetter, DeadLetterReason, DeadLetters};
+use super::mailbox::Rejected;
+use super::scheduler;
+use super::task::Map;
 
-use std::boxed::FnBox;
-use std::sync::Weak;
+use std::any::type_name;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 
 use crossbeam_channel as channel;
+use uuid::Uuid;
 
 // ---
 // Address
 // ---
 pub struct Address<A: Actor> {
+    uuid: Uuid,
     cell_ref: Weak<Cell<A>>,
-    postman: channel::Sender<Box<FnBox()>>,
+    dead_letters: Arc<DeadLetters>,
 }
 impl<A: Actor + 'static> Address<A> {
-    pub(crate) fn new(cell: Weak<Cell<A>>, tx: channel::Sender<Box<FnBox()>>) -> Self {
+    pub(crate) fn new(uuid: Uuid, cell: Weak<Cell<A>>, dead_letters: Arc<DeadLetters>) -> Self {
         Address {
+            uuid,
             cell_ref: cell,
-            postman: tx,
+            dead_letters,
         }
     }
 
-    pub fn send<M: 'static>(&self, msg: M)
+    /// The unique id of the actor behind this address. This is the id reported in a
+    /// `Terminated` notification when watching the actor (see `Context::watch`).
+    pub fn id(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub(crate) fn cell(&self) -> Option<Arc<Cell<A>>> {
+        Weak::upgrade(&self.cell_ref)
+    }
+
+    /// The number of messages sent to this actor that were dropped or refused because its
+    /// mailbox was full. See `romeo::mailbox::Overflow`.
+    pub fn overflow_count(&self) -> usize {
+        self.cell().map_or(0, |cell| cell.mailbox().overflows())
+    }
+
+    /// Send a message to the actor without waiting for it to be processed. If the actor is no
+    /// longer running, or the message is dropped because the actor's mailbox is full, the
+    /// message is published to the system's `DeadLetters`.
+    ///
+    /// If the actor's mailbox is full and uses `Overflow::Block`, this blocks until there is
+    /// room for the message, unless it is sent from a scheduler thread (see `Overflow::Block`).
+    /// Messages have to be `Send`, as they are handed over to the actor's scheduler thread.
+    pub fn send<M: Send + 'static>(&self, msg: M)
+    where
+        A: Receives<M>,
+    {
+        if let Err(err) = self.post(msg, Self::wrap, true) {
+            self.dead_letter::<M>(err.reason());
+        }
+    }
+
+    /// Send a message to the actor, handing the message back inside of a `SendError` if it
+    /// cannot be delivered. This never blocks, even when the actor's mailbox is full.
+    pub fn try_send<M: Send + 'static>(&self, msg: M) -> Result<(), SendError<M>>
+    where
+        A: Receives<M>,
+    {
+        match self.post(msg, Self::wrap, false) {
+            Ok(()) => Ok(()),
+            Err(Rejected::Closed(msg)) => Err(SendError::Stopped(msg)),
+            Err(Rejected::Full(msg)) => Err(SendError::Full(msg)),
+            Err(rejected @ Rejected::Dropped(_)) => {
+                self.dead_letter::<M>(rejected.reason());
+                Ok(())
+            }
+        }
+    }
+
+    /// Send a message without ever blocking, publishing it as a dead letter if the actor's
+    /// mailbox is full. Used where the sender must not be held up, such as by timers (see
+    /// `Context::schedule_once_to`).
+    pub(crate) fn send_without_blocking<M: Send + 'static>(&self, msg: M)
+    where
+        A: Receives<M>,
+    {
+        if let Err(err) = self.post(msg, Self::wrap, false) {
+            self.dead_letter::<M>(err.reason());
+        }
+    }
+
+    /// Deliver the `Terminated` notification of an actor this actor watches, see
+    /// `Context::watch`. The notification is dropped if this actor has stopped watching by the
+    /// time it gets around to it, so that no notification is handled after `Context::unwatch`.
+    ///
+    /// This is sent from the scheduler of the stopped actor, so it never blocks. A notification
+    /// that does not fit the mailbox is published as a dead letter.
+    pub(crate) fn notify_terminated(&self, terminated: Terminated)
+    where
+        A: Receives<Terminated>,
+    {
+        let wrap = |terminated: Terminated| {
+            let envelope = Self::wrap(terminated);
+            Envelope::new::<Terminated, _>(move |act: &mut A, ctx: &Context<A>| {
+                if ctx.cell().remove_watching(terminated.id) {
+                    envelope.handle(act, ctx)
+                } else {
+                    Ok(())
+                }
+            })
+        };
+        if let Err(err) = self.post(terminated, wrap, false) {
+            if let Some(cell) = self.cell() {
+                cell.remove_watching(terminated.id);
+            }
+            self.dead_letter::<Terminated>(err.reason());
+        }
+    }
+
+    /// Send a message to the actor and get back a `Reply` that will eventually hold the value
+    /// returned by the actor's handler. Unlike `send`, this allows for a request-response style
+    /// of communication without having to pass channels around inside of messages.
+    ///
+    /// If the actor is no longer running (or stops before handling the message) the reply
+    /// resolves to `AskError::Stopped` rather than waiting forever. If the handler fails, the
+    /// reply resolves to `AskError::Failed`. The message and the response have to be `Send`, as
+    /// they are handed over to and from the actor's scheduler thread.
+    pub fn ask<M: Send + 'static>(&self, msg: M) -> Reply<<A as Receives<M>>::Response>
     where
         A: Receives<M>,
+        <A as Receives<M>>::Response: Send + 'static,
     {
-        if let Some(cell) = Weak::upgrade(&self.cell_ref) {
-            let lambda_cell = cell.clone();
-            let receive: Box<FnBox()> = Box::new(move || -> () {
-                let actor_mutex = lambda_cell.actor_ref();
-                let mut act = actor_mutex.lock().unwrap();
-                act.receive(msg, &lambda_cell.context());
-            });
-            self.postman.send(receive);
-        } else {
-            // TODO: raise some kind of error if the address is no longer valid (the cell
-            //       it points to not longer exists)
+        self.request(msg, Self::wrap_ask)
+    }
+
+    /// Send a message to be handled by the actor's async handler (see `ReceivesAsync`). This is
+    /// otherwise the same as `send`.
+    pub fn send_async<M: Send + 'static>(&self, msg: M)
+    where
+        A: ReceivesAsync<M>,
+        <A as ReceivesAsync<M>>::Response: 'static,
+    {
+        if let Err(err) = self.post(msg, Self::wrap_async, true) {
+            self.dead_letter::<M>(err.reason());
+        }
+    }
+
+    /// Send a message to be handled by the actor's async handler (see `ReceivesAsync`), and get
+    /// back a `Reply` that resolves once the handler's future has completed. This is otherwise
+    /// the same as `ask`.
+    pub fn ask_async<M: Send + 'static>(&self, msg: M) -> Reply<<A as ReceivesAsync<M>>::Response>
+    where
+        A: ReceivesAsync<M>,
+        <A as ReceivesAsync<M>>::Response: Send + 'static,
+    {
+        self.request(msg, Self::wrap_ask_async)
+    }
+
+    /// Send a message packaged up by `wrap` along with the sending half of the reply channel,
+    /// see `ask`.
+    fn request<M: 'static, R, F>(&self, msg: M, wrap: F) -> Reply<R>
+    where
+        F: FnOnce(M, Responder<R>) -> Envelope<A>,
+    {
+        let (tx, rx) = channel::bounded(1);
+        let refused = tx.clone();
+        if let Err(rejected) = self.post(msg, move |msg| wrap(msg, tx), true) {
+            // a closed mailbox resolves the reply as stopped, once the senders have been dropped
+            if let Rejected::Full(_) | Rejected::Dropped(_) = rejected {
+                refused.send(Err(AskError::MailboxFull));
+            }
+            self.dead_letter::<M>(rejected.reason());
+        }
+        Reply { response: rx }
+    }
+
+    /// Package a message up for delivery to the actor
+    fn wrap<M: 'static>(msg: M) -> Envelope<A>
+    where
+        A: Receives<M>,
+    {
+        Envelope::new::<M, _>(move |act: &mut A, ctx: &Context<A>| {
+            let (result, _) = stashable(ctx, Box::new(Self::wrap), move || receive(act, msg, ctx));
+            result.map(|_| ())
+        })
+    }
+
+    /// Package a message up for delivery to the actor, replying with the outcome of handling it
+    /// (see `ask`). A stashed message is replied to once it has been unstashed and handled.
+    fn wrap_ask<M: 'static>(msg: M, tx: Responder<<A as Receives<M>>::Response>) -> Envelope<A>
+    where
+        A: Receives<M>,
+    {
+        Envelope::new::<M, _>(move |act: &mut A, ctx: &Context<A>| {
+            let again = tx.clone();
+            let rewrap = Box::new(move |msg| Self::wrap_ask(msg, again));
+            let (result, stashed) = stashable(ctx, rewrap, move || receive(act, msg, ctx));
+            if stashed {
+                result.map(|_| ())
+            } else {
+                respond(&tx, result)
+            }
+        })
+    }
+
+    /// Package a message up for delivery to the actor's async handler
+    fn wrap_async<M: 'static>(msg: M) -> Envelope<A>
+    where
+        A: ReceivesAsync<M>,
+    {
+        Envelope::new::<M, _>(move |act: &mut A, ctx: &Context<A>| {
+            let (handling, stashed) = stashable(ctx, Box::new(Self::wrap_async), move || act.receive_async(msg, ctx));
+            if !stashed {
+                ctx.cell().set_task(Box::pin(Map::new(handling, |result| result.map(|_| ()))));
+            }
+            Ok(())
+        })
+    }
+
+    /// Package a message up for delivery to the actor's async handler, replying with the outcome
+    /// of handling it (see `ask_async`)
+    fn wrap_ask_async<M: 'static>(msg: M, tx: Responder<<A as ReceivesAsync<M>>::Response>) -> Envelope<A>
+    where
+        A: ReceivesAsync<M>,
+        <A as ReceivesAsync<M>>::Response: Send,
+    {
+        Envelope::new::<M, _>(move |act: &mut A, ctx: &Context<A>| {
+            let again = tx.clone();
+            let rewrap = Box::new(move |msg| Self::wrap_ask_async(msg, again));
+            let (handling, stashed) = stashable(ctx, rewrap, move || act.receive_async(msg, ctx));
+            if !stashed {
+                ctx.cell().set_task(Box::pin(Map::new(handling, move |result| respond(&tx, result))));
+            }
+            Ok(())
+        })
+    }
+
+    /// Hand a message over to the actor's mailbox, applying the mailbox's overflow policy.
+    /// Messages evicted from the mailbox to make room are published as dead letters.
+    ///
+    /// A sender on a scheduler thread is never blocked, see `Overflow::Block`.
+    fn post<M, F>(&self, msg: M, wrap: F, may_block: bool) -> Result<(), Rejected<M>>
+    where
+        F: FnOnce(M) -> Envelope<A>,
+    {
+        let cell = match self.cell() {
+            Some(cell) => cell,
+            None => return Err(Rejected::Closed(msg)),
+        };
+        let may_block = may_block && !scheduler::is_running_actors();
+        match cell.mailbox().push(msg, wrap, may_block) {
+            Ok(None) => {
+                cell.wake();
+                Ok(())
+            }
+            Ok(Some(evicted)) => {
+                cell.wake();
+                self.dead_letters.publish(DeadLetter {
+                    recipient: self.uuid,
+                    message_type: evicted.message_type,
+                    reason: DeadLetterReason::MailboxOverflow,
+                });
+                Ok(())
+            }
+            Err(rejected) => Err(rejected),
+        }
+    }
+
+    fn dead_letter<M: 'static>(&self, reason: DeadLetterReason) {
+        self.dead_letters.publish(DeadLetter {
+            recipient: self.uuid,
+            message_type: type_name::<M>(),
+            reason,
+        });
+    }
+}
+
+/// The sending half of the channel an `Address::ask` is replied to over
+type Responder<R> = channel::Sender<Result<R, AskError>>;
+
+/// Handle a message with the actor's current behavior, catching a panic of the handler so that
+/// the caller of `Address::ask` learns of it (see `cell::catch_panic`)
+fn receive<A, M: 'static>(act: &mut A, msg: M, ctx: &Context<A>) -> Result<<A as Receives<M>>::Response, Failure>
+where
+    A: Actor + Receives<M> + 'static,
+{
+    cell::catch_panic(ctx.cell().uuid(), move || behavior::receive(act, msg, ctx))
+}
+
+/// Handle a message in a way that allows the handler to stash it (see `Context::stash`),
+/// returning the outcome of `handle` along with whether the message was stashed. A stashed
+/// message is packaged up again with `rewrap`.
+fn stashable<A, M, R, F>(ctx: &Context<A>, rewrap: Rewrap<A, M>, handle: F) -> (R, bool)
+where
+    A: Actor + 'static,
+    M: 'static,
+    F: FnOnce() -> R,
+{
+    let cell = ctx.cell();
+    cell.begin_handling(rewrap);
+    let outcome = handle();
+    (outcome, cell.end_handling())
+}
+
+/// Hand the outcome of handling a message back to the caller of `Address::ask`, passing on the
+/// outcome to the actor's cell
+fn respond<R>(tx: &Responder<R>, result: Result<R, Failure>) -> Result<(), Failure> {
+    match result {
+        Ok(response) => {
+            tx.send(Ok(response));
+            Ok(())
+        }
+        Err(failure) => {
+            tx.send(Err(AskError::Failed(failure.to_string())));
+            Err(failure)
         }
-        // TODO: Need to wrap this in a mutex to ensure serial access to the mutable reference
     }
 }
 
@@ -45,11 +325,101 @@ impl<A: Actor + 'static> Address<A> {
 impl<A: Actor> Clone for Address<A> {
     fn clone(&self) -> Self {
         Address {
+            uuid: self.uuid,
             cell_ref: self.cell_ref.clone(),
-            postman: self.postman.clone(),
+            dead_letters: self.dead_letters.clone(),
         }
     }
 }
 
 unsafe impl<A: Actor> Send for Address<A> {}
 unsafe impl<A: Actor> Sync for Address<A> {}
+
+// ---
+// Reply
+// ---
+
+/// Reply is the handle returned from `Address::ask`. It can either be waited on (with a timeout)
+/// or polled without blocking the current thread. Once a value has been taken from the reply,
+/// the reply is spent.
+pub struct Reply<R> {
+    response: channel::Receiver<Result<R, AskError>>,
+}
+impl<R> Reply<R> {
+    /// Block the current thread until the actor has replied, or until `timeout` has elapsed.
+    pub fn wait_timeout(self, timeout: Duration) -> Result<R, AskError> {
+        select! {
+            recv(self.response, msg) => msg.unwrap_or(Err(AskError::Stopped)),
+            recv(channel::after(timeout)) => Err(AskError::Timeout),
+        }
+    }
+
+    /// Check for a reply without blocking. Returns `Ok(None)` if the actor has not replied yet.
+    pub fn poll(&self) -> Result<Option<R>, AskError> {
+        select! {
+            recv(self.response, msg) => msg.unwrap_or(Err(AskError::Stopped)).map(Some),
+            default => Ok(None),
+        }
+    }
+}
+
+/// The ways in which an `Address::ask` can fail to produce a reply
+#[derive(Debug, PartialEq, Eq)]
+pub enum AskError {
+    /// No reply was received within the given timeout
+    Timeout,
+    /// The actor stopped (or was already stopped) before it could reply
+    Stopped,
+    /// The actor's handler returned an error, described by the contained message
+    Failed(String),
+    /// The actor's mailbox was full and the message was dropped or refused
+    MailboxFull,
+}
+impl Display for AskError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            AskError::Timeout => write!(f, "timed out waiting for a reply"),
+            AskError::Stopped => write!(f, "actor stopped before replying"),
+            AskError::Failed(reason) => write!(f, "actor failed to handle message: {}", reason),
+            AskError::MailboxFull => write!(f, "actor's mailbox is full"),
+        }
+    }
+}
+impl Error for AskError {}
+
+// ---
+// SendError
+// ---
+
+/// The error returned from `Address::try_send`, which hands back the undelivered message.
+pub enum SendError<M> {
+    /// The actor is no longer running
+    Stopped(M),
+    /// The actor's mailbox is full (see `romeo::mailbox::Overflow`)
+    Full(M),
+}
+impl<M> SendError<M> {
+    /// Take back the message that could not be delivered
+    pub fn into_inner(self) -> M {
+        match self {
+            SendError::Stopped(msg) | SendError::Full(msg) => msg,
+        }
+    }
+}
+impl<M> Debug for SendError<M> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            SendError::Stopped(_) => write!(f, "Stopped(..)"),
+            SendError::Full(_) => write!(f, "Full(..)"),
+        }
+    }
+}
+impl<M> Display for SendError<M> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            SendError::Stopped(_) => write!(f, "actor is no longer running"),
+            SendError::Full(_) => write!(f, "actor's mailbox is full"),
+        }
+    }
+}
+impl<M> Error for SendError<M> {}
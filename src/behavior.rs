@@ -0,0 +1,75 @@
+use super::actor::{Actor, Context, Failure, Receives};
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+type Handler<A, M> = Box<dyn Fn(&mut A, M, &Context<A>) -> Result<<A as Receives<M>>::Response, Failure> + Send>;
+
+/// Behavior is a set of message handlers that an actor can switch to at runtime, replacing its
+/// `Receives` handlers for the types of message the behavior handles. Messages of any other
+/// type are still handled by the actor's `Receives` impls. This allows an actor to model a
+/// protocol as a series of behaviors, such as "connecting", then "connected", then "draining".
+///
+/// Behaviors are switched with `Context::become_behavior`, `Context::push_behavior` and
+/// `Context::unbecome`. A restarted actor starts out without a behavior.
+///
+/// Note that a behavior can only handle types of message the actor has a `Receives` impl for,
+/// as sending a message to an actor requires one (and the impl determines the response type).
+/// Handlers have to be `Send`, as they move along with their actor when the actor is stolen by
+/// another scheduler.
+pub struct Behavior<A: Actor> {
+    /// The handlers by type of message, each a `Handler<A, M>`
+    handlers: HashMap<TypeId, Box<dyn Any + Send>>,
+    actor: PhantomData<A>,
+}
+impl<A: Actor + 'static> Behavior<A> {
+    pub fn new() -> Self {
+        Behavior {
+            handlers: HashMap::new(),
+            actor: PhantomData,
+        }
+    }
+
+    /// Handle messages of type `M` with `handler` while this behavior is active, replacing any
+    /// handler added before for `M`.
+    pub fn on<M: 'static, F>(mut self, handler: F) -> Self
+    where
+        A: Receives<M>,
+        F: Fn(&mut A, M, &Context<A>) -> Result<<A as Receives<M>>::Response, Failure> + Send + 'static,
+    {
+        let handler: Handler<A, M> = Box::new(handler);
+        self.handlers.insert(TypeId::of::<M>(), Box::new(handler));
+        self
+    }
+
+    /// Handle a message with this behavior, falling back on the actor's `Receives` impl if the
+    /// behavior does not handle messages of type `M`.
+    fn receive<M: 'static>(&self, actor: &mut A, msg: M, ctx: &Context<A>) -> Result<<A as Receives<M>>::Response, Failure>
+    where
+        A: Receives<M>,
+    {
+        let handler = self.handlers.get(&TypeId::of::<M>()).and_then(|h| h.downcast_ref::<Handler<A, M>>());
+        match handler {
+            Some(handler) => handler(actor, msg, ctx),
+            None => actor.receive(msg, ctx),
+        }
+    }
+}
+
+impl<A: Actor + 'static> Default for Behavior<A> {
+    fn default() -> Self {
+        Behavior::new()
+    }
+}
+
+/// Handle a message with the actor's current behavior, or its `Receives` impl if it has none
+pub(crate) fn receive<A, M: 'static>(actor: &mut A, msg: M, ctx: &Context<A>) -> Result<<A as Receives<M>>::Response, Failure>
+where
+    A: Actor + Receives<M> + 'static,
+{
+    match ctx.cell().behavior() {
+        Some(behavior) => behavior.receive(actor, msg, ctx),
+        None => actor.receive(msg, ctx),
+    }
+}
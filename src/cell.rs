@@ -1,53 +1,425 @@
-use super::actor::{self, Actor, Context};
+use super::actor::{
+    self, Actor, ActorConfig, Context, Directive, Failure, Handling, Panicked, RestartReason, StashFull, Terminated,
+};
 use super::address::Address;
+use super::behavior::Behavior;
+use super::dead_letters::{DeadLetter, DeadLetterReason, DeadLetters};
+use super::mailbox::{Mailbox, MailboxRetention};
 use super::scheduler::Scheduler;
+use super::supervision::{Restarts, Supervision};
+use super::task::Task;
+use super::timer::Timer;
 
-use std::boxed::FnBox;
+use std::any::{type_name, Any};
+use std::backtrace::Backtrace;
 use std::cell::RefCell;
-use std::sync::{Arc, Mutex, Weak};
+use std::collections::{HashMap, VecDeque};
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once, Weak};
+use std::task::Poll;
+use std::time::Duration;
 
-use crossbeam_channel as channel;
 use uuid::Uuid;
 
+/// A message bound for an actor of type `A`, packaged up as a closure that applies the message
+/// to the actor once the cell gets around to processing it. The type of the message is kept
+/// around for reporting the message as a dead letter if it is never processed.
+type Handler<A> = Box<dyn FnOnce(&mut A, &Context<A>) -> Result<(), Failure>>;
+
+pub(crate) struct Envelope<A: Actor> {
+    pub(crate) message_type: &'static str,
+    handler: Handler<A>,
+}
+impl<A: Actor + 'static> Envelope<A> {
+    pub(crate) fn new<M: 'static, F>(handler: F) -> Self
+    where
+        F: FnOnce(&mut A, &Context<A>) -> Result<(), Failure> + 'static,
+    {
+        Envelope {
+            message_type: type_name::<M>(),
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Apply the message to the actor
+    pub(crate) fn handle(self, actor: &mut A, ctx: &Context<A>) -> Result<(), Failure> {
+        (self.handler)(actor, ctx)
+    }
+}
+
+/// Packages a message of type `M` back up into an envelope, so that the message being handled can
+/// be stashed (see `romeo::actor::Context::stash`)
+pub(crate) type Rewrap<A, M> = Box<dyn FnOnce(M) -> Envelope<A>>;
+
+// ---
+// Panic Isolation
+// ---
+
+thread_local! {
+    /// Set while `catch_panic` runs actor code on this thread
+    static CATCHING: RefCell<bool> = const { RefCell::new(false) };
+    /// The backtrace of the latest panic of actor code on this thread, see `catch_panic`
+    static PANIC_BACKTRACE: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
+}
+static PANIC_HOOK: Once = Once::new();
+
+/// Install the panic hook that captures the backtraces logged by `catch_panic`, see
+/// `Config::panic_backtraces`. Only panics of actor code are captured, and every panic is passed
+/// on to the hook that was installed before.
+pub(crate) fn capture_panic_backtraces() {
+    PANIC_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if CATCHING.with(|catching| *catching.borrow()) {
+                PANIC_BACKTRACE.with(|backtrace| *backtrace.borrow_mut() = Some(Backtrace::force_capture()));
+            }
+            previous(info);
+        }));
+    });
+}
+
+/// Run actor code (a handler or a lifecycle hook), turning a panic into a `Panicked` failure of
+/// the actor so that it is handled like any other failure. This keeps a panicking actor from
+/// taking down its scheduler's thread, along with every other actor on it.
+///
+/// The panic is logged, along with its backtrace if `Config::panic_backtraces` is set.
+pub(crate) fn catch_panic<T, F>(actor: Uuid, handler: F) -> Result<T, Failure>
+where
+    F: FnOnce() -> Result<T, Failure>,
+{
+    let catching = CATCHING.with(|catching| catching.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(handler));
+    CATCHING.with(|flag| *flag.borrow_mut() = catching);
+
+    match result {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = match payload.downcast_ref::<&str>() {
+                Some(message) => message.to_string(),
+                None => payload.downcast_ref::<String>().cloned().unwrap_or_else(|| "Box<Any>".to_string()),
+            };
+            let backtrace = PANIC_BACKTRACE.with(|backtrace| backtrace.borrow_mut().take());
+            match backtrace {
+                Some(backtrace) => error!("Actor-cell {} panicked: {}\n{}", actor, message, backtrace),
+                None => error!("Actor-cell {} panicked: {}", actor, message),
+            }
+            Err(Box::new(Panicked { message }))
+        }
+    }
+}
+
+/// The outcome of a cell working through a batch of messages, see `ACell::process`
+pub(crate) struct Batch {
+    /// The number of messages handled
+    pub(crate) handled: usize,
+    /// The failure of the actor's handler that ended the batch early, if any
+    pub(crate) failure: Option<Failure>,
+}
+
+/// Delivers a `Terminated` notification to a watching actor
+pub(crate) type Notify = Box<dyn Fn(Terminated)>;
+
+/// SystemMessage is a lifecycle, supervision or death-watch signal for a cell. System messages
+/// are kept in a queue of their own, separate from the actor's mailbox, and a cell's system
+/// messages are always handled (in the order they were sent) before its next user message.
+/// They are handled by the cell's scheduler, see `Scheduler::handle_system_message`.
+pub(crate) enum SystemMessage {
+    Start,
+    Restart(RestartReason),
+    /// Replace a restarting actor with a fresh instance, once its restart backoff has passed
+    Recreate(RestartReason),
+    /// Stop the actor, once all of its children have stopped
+    Stop,
+    /// The actor failed on behalf of one of its children, see `supervision::handle_failure`
+    Failed(Failure),
+    /// A child of the actor has stopped and been removed from its scheduler
+    ChildTerminated(Uuid),
+    Watch(Uuid, Notify),
+    Unwatch(Uuid),
+}
+
 // ---
 // Cell
 // ---
 pub(crate) struct Cell<A: Actor> {
     uuid: Uuid,
     actor: Arc<Mutex<A>>,
-    actor_running_state: RefCell<actor::State>,
-    actor_producer: Box<Fn() -> A>,
-    mailbox: channel::Receiver<Box<FnBox()>>,
-    postman: channel::Sender<Box<FnBox()>>,
+    /// Read by other threads (such as a scheduler about to steal the cell), so kept behind a lock
+    actor_running_state: Mutex<actor::State>,
+    actor_producer: Box<dyn Fn() -> A>,
+    mailbox: Mailbox<Envelope<A>>,
+    /// The async handler the actor is waiting on, which holds up the mailbox until it completes
+    task: Mutex<Option<Task>>,
+    /// Messages set aside by the actor, see `romeo::actor::Context::stash`
+    stash: Mutex<VecDeque<Envelope<A>>>,
+    stash_capacity: usize,
+    /// The `Rewrap<A, M>` of the message being handled, which is taken once it is stashed
+    handling: Mutex<Option<Box<dyn Any>>>,
+    /// The type of the last message the actor began handling, reported to `Actor::pre_restart`
+    last_message: Mutex<Option<&'static str>>,
+    /// The restarts of the actor, which may be held back (see `ActorConfig::restart_backoff`)
+    restarts: Mutex<Restarts>,
+    /// Set between `Actor::pre_restart` and the fresh actor replacing the old one
+    restart_pending: AtomicBool,
+    /// Set while the actor waits out its restart backoff, see `recreate_after`
+    backing_off: AtomicBool,
+    mailbox_retention: MailboxRetention,
+    /// Pending system messages, which becomes `None` once the cell has terminated
+    system: Mutex<Option<VecDeque<SystemMessage>>>,
+
+    /// The scheduler the cell currently lives on, which changes when the cell is stolen by
+    /// another scheduler (see `Scheduler::steal`)
+    parent_scheduler: Mutex<Weak<Scheduler>>,
+    /// Set while the cell is on a run queue or being run by its scheduler, see `Cell::schedule`
+    scheduled: AtomicBool,
+    supervision: Supervision,
+    /// A weak reference to the cell itself, handed to contexts so that actors can become
+    /// supervisors of the actors they create
+    myself: Mutex<Weak<Cell<A>>>,
 
-    parent_scheduler: Weak<Scheduler>,
+    /// Actors watching this cell, keyed by the watcher's UUID. Once the cell has terminated this
+    /// becomes `None`, and any new watchers are notified immediately.
+    watchers: Mutex<Option<HashMap<Uuid, Notify>>>,
+    /// Cells this cell is watching, so that the watches can be removed when this cell stops. A
+    /// cell is forgotten about once its `Terminated` notification has been handled.
+    watching: Mutex<HashMap<Uuid, Weak<dyn ACell>>>,
+    /// Timers scheduled by the actor, cancelled when the actor stops or restarts
+    timers: Mutex<Vec<Timer>>,
+    /// The behaviors the actor has switched to, the last of which is current
+    behaviors: Mutex<Vec<Arc<Behavior<A>>>>,
+    dead_letters: Arc<DeadLetters>,
 }
 
 impl<A: Actor + 'static> Cell<A> {
-    pub(crate) fn new(actor_producer: Box<Fn() -> A>, scheduler: Weak<Scheduler>) -> Arc<Self> {
-        let (tx, rx) = channel::unbounded::<Box<FnBox()>>();
-        Arc::new(Cell {
+    pub(crate) fn new(
+        actor_producer: Box<dyn Fn() -> A>,
+        config: &ActorConfig,
+        scheduler: Weak<Scheduler>,
+        supervisor: Option<Weak<dyn ACell>>,
+        dead_letters: Arc<DeadLetters>,
+    ) -> Arc<Self> {
+        let cell = Arc::new(Cell {
             uuid: Uuid::new_v4(),
             actor: Arc::new(Mutex::new(actor_producer())),
-            actor_running_state: RefCell::new(actor::State::Starting),
+            actor_running_state: Mutex::new(actor::State::Starting),
             actor_producer,
-            mailbox: rx,
-            postman: tx,
+            mailbox: Mailbox::new(config.mailbox),
+            task: Mutex::new(None),
+            stash: Mutex::new(VecDeque::new()),
+            stash_capacity: config.stash_capacity,
+            handling: Mutex::new(None),
+            last_message: Mutex::new(None),
+            restarts: Mutex::new(Restarts::new(config.restart_backoff)),
+            restart_pending: AtomicBool::new(false),
+            backing_off: AtomicBool::new(false),
+            mailbox_retention: config.mailbox_retention,
+            system: Mutex::new(Some(VecDeque::new())),
 
-            parent_scheduler: scheduler,
-        })
+            parent_scheduler: Mutex::new(scheduler),
+            scheduled: AtomicBool::new(false),
+            supervision: Supervision::new(supervisor),
+            myself: Mutex::new(Weak::new()),
+
+            watchers: Mutex::new(Some(HashMap::new())),
+            watching: Mutex::new(HashMap::new()),
+            timers: Mutex::new(vec![]),
+            behaviors: Mutex::new(vec![]),
+            dead_letters,
+        });
+        *cell.myself.lock().unwrap() = Arc::downgrade(&cell);
+        cell
+    }
+
+    fn state(&self) -> actor::State {
+        self.actor_running_state.lock().unwrap().clone()
+    }
+
+    fn set_state(&self, state: actor::State) {
+        *self.actor_running_state.lock().unwrap() = state;
     }
 
     pub(crate) fn actor_ref(&self) -> Arc<Mutex<A>> {
         self.actor.clone()
     }
 
-    pub(crate) fn context(&self) -> Context {
-        Context::new(self.uuid, self.actor_running_state.borrow().clone(), self.parent_scheduler.clone())
+    pub(crate) fn context(&self) -> Context<A> {
+        Context::new(
+            self.uuid,
+            self.state(),
+            self.parent_scheduler.lock().unwrap().clone(),
+            self.myself.lock().unwrap().clone(),
+        )
     }
 
     pub(crate) fn address(cell: Arc<Self>) -> Address<A> {
-        Address::new(Arc::downgrade(&cell), (&cell).postman.clone())
+        Address::new(
+            cell.uuid,
+            Arc::downgrade(&cell),
+            cell.dead_letters.clone(),
+        )
+    }
+
+    pub(crate) fn mailbox(&self) -> &Mailbox<Envelope<A>> {
+        &self.mailbox
+    }
+
+    /// Put the cell on its scheduler's run queue after new work has been enqueued for it,
+    /// unless it already is on a run queue (or being run), in which case the work is picked up
+    /// when the cell is next run.
+    pub(crate) fn wake(&self) {
+        ACell::schedule(self);
+    }
+
+    /// Record that this cell is watching the cell with the given UUID, see
+    /// `romeo::actor::Context::watch`.
+    pub(crate) fn add_watching(&self, uuid: Uuid, cell: Weak<dyn ACell>) {
+        self.watching.lock().unwrap().insert(uuid, cell);
+    }
+
+    /// Forget about watching the cell with the given UUID, returning whether it was watched
+    pub(crate) fn remove_watching(&self, uuid: Uuid) -> bool {
+        self.watching.lock().unwrap().remove(&uuid).is_some()
+    }
+
+    /// Wait on the future of an async handler before handling any further messages, see
+    /// `romeo::actor::ReceivesAsync`.
+    pub(crate) fn set_task(&self, future: Handling<()>) {
+        let cell: Weak<dyn ACell> = self.myself.lock().unwrap().clone();
+        *self.task.lock().unwrap() = Some(Task::new(future, cell));
+    }
+
+    /// Poll the async handler the actor is waiting on, which is ready right away if there is none
+    fn poll_task(&self) -> Poll<Result<(), Failure>> {
+        let mut task = self.task.lock().unwrap();
+        let poll = match task.as_mut() {
+            Some(task) => match catch_panic(self.uuid, || Ok(task.poll())) {
+                Ok(poll) => poll,
+                Err(failure) => Poll::Ready(Err(failure)),
+            },
+            None => return Poll::Ready(Ok(())),
+        };
+        if poll.is_ready() {
+            *task = None;
+        }
+        poll
+    }
+
+    /// Note the message about to be handled, see `Cell::stash`
+    pub(crate) fn begin_handling<M: 'static>(&self, rewrap: Rewrap<A, M>) {
+        *self.handling.lock().unwrap() = Some(Box::new(rewrap));
+    }
+
+    /// Forget about the message that was just handled, returning whether it was stashed
+    pub(crate) fn end_handling(&self) -> bool {
+        self.handling.lock().unwrap().take().is_none()
+    }
+
+    /// Set the message being handled aside, see `romeo::actor::Context::stash`. The message is
+    /// handed back if the stash is full.
+    pub(crate) fn stash<M: 'static>(&self, msg: M) -> Result<(), StashFull<M>> {
+        let mut handling = self.handling.lock().unwrap();
+        let rewrap = match handling.take().map(|rewrap| rewrap.downcast::<Rewrap<A, M>>()) {
+            Some(Ok(rewrap)) => rewrap,
+            Some(Err(other)) => {
+                *handling = Some(other);
+                panic!("Only the message being handled can be stashed")
+            }
+            None => panic!("Only the message being handled can be stashed"),
+        };
+
+        let mut stash = self.stash.lock().unwrap();
+        if stash.len() >= self.stash_capacity {
+            *handling = Some(rewrap);
+            return Err(StashFull(msg));
+        }
+        let rewrap: Rewrap<A, M> = *rewrap;
+        stash.push_back(rewrap(msg));
+        Ok(())
+    }
+
+    /// Put all stashed messages back at the front of the mailbox, in the order they were stashed
+    pub(crate) fn unstash_all(&self) {
+        let stashed: Vec<_> = self.stash.lock().unwrap().drain(..).collect();
+        if stashed.is_empty() {
+            return;
+        }
+        match self.mailbox.push_front_all(stashed) {
+            Ok(()) => self.wake(),
+            Err(stashed) => self.dead_letters(stashed, DeadLetterReason::Stopped),
+        }
+    }
+
+    /// Drop everything the actor has set up that should not outlive it: its pending async
+    /// handler, its behaviors and its timers. This happens when the actor stops or restarts.
+    fn release(&self) {
+        self.task.lock().unwrap().take();
+        self.behaviors.lock().unwrap().clear();
+        for timer in self.timers.lock().unwrap().drain(..) {
+            timer.cancel();
+        }
+    }
+
+    /// Publish messages that will never be processed as dead letters
+    fn dead_letters(&self, envelopes: Vec<Envelope<A>>, reason: DeadLetterReason) {
+        for envelope in envelopes {
+            self.dead_letters.publish(DeadLetter {
+                recipient: self.uuid,
+                message_type: envelope.message_type,
+                reason,
+            });
+        }
+    }
+
+    /// Have the fresh actor replace the old one after `delay`, holding up the mailbox meanwhile.
+    /// The timer is cancelled should the actor stop (or restart again) in the meantime.
+    fn recreate_after(&self, delay: Duration, reason: RestartReason) -> Result<(), Failure> {
+        let runtime = self.scheduler().and_then(|s| s.runtime());
+        let runtime = match runtime {
+            Some(runtime) => runtime,
+            None => return self.recreate(reason),
+        };
+        debug!("Actor-cell {} restarts in {:?}", self.uuid, delay);
+        self.backing_off.store(true, Ordering::SeqCst);
+        let myself = self.myself.lock().unwrap().clone();
+        let timer = runtime.timers().schedule(delay, None, Box::new(move || {
+            if let Some(cell) = Weak::upgrade(&myself) {
+                cell.tell(SystemMessage::Recreate(reason.clone()));
+            }
+        }));
+        self.add_timer(timer);
+        Ok(())
+    }
+
+    /// The actor's current behavior, see `romeo::behavior::Behavior`
+    pub(crate) fn behavior(&self) -> Option<Arc<Behavior<A>>> {
+        self.behaviors.lock().unwrap().last().cloned()
+    }
+
+    /// Switch the actor to a behavior, either on top of the current behavior or replacing it
+    pub(crate) fn become_behavior(&self, behavior: Behavior<A>, keep_current: bool) {
+        let mut behaviors = self.behaviors.lock().unwrap();
+        if !keep_current {
+            behaviors.pop();
+        }
+        behaviors.push(Arc::new(behavior));
+    }
+
+    /// Return to the behavior the actor had before its current one
+    pub(crate) fn unbecome(&self) {
+        self.behaviors.lock().unwrap().pop();
+    }
+
+    /// Record a timer scheduled by the actor, see `romeo::actor::Context::schedule_once`.
+    pub(crate) fn add_timer(&self, timer: Timer) {
+        let mut timers = self.timers.lock().unwrap();
+        timers.retain(Timer::is_active);
+        timers.push(timer);
+    }
+
+    fn has_system_messages(&self) -> bool {
+        self.system.lock().unwrap().as_ref().is_some_and(|q| !q.is_empty())
     }
 
     /// Replace the current actor state with the `actor_producer` constructor
@@ -65,49 +437,307 @@ unsafe impl<A: Actor> Sync for Cell<A> {}
 // ACell, a parameter-type-less cell for the runtime
 // ---
 pub(crate) trait ACell: Send + Sync {
-    fn process(&self) -> bool;
+    /// Process up to `budget` messages, as long as the actor is running. The batch ends early
+    /// when the mailbox runs dry, when the actor's handler fails, or when a system message (such
+    /// as a stop or restart requested by the handler) is waiting to be handled.
+    fn process(&self, budget: usize) -> Batch;
+    /// Send a system message to the cell. System messages sent after the cell has terminated
+    /// are dropped, apart from watches which are answered right away.
+    fn tell(&self, msg: SystemMessage);
+    /// Take the system messages waiting for the cell, in the order they were sent
+    fn take_system_messages(&self) -> VecDeque<SystemMessage>;
+    /// Whether the cell has been asked to stop and is waiting on its children to stop
+    fn is_stopping(&self) -> bool;
+    /// Whether the cell has no system or user messages waiting to be handled. A cell waiting out
+    /// its restart backoff is idle, as its mailbox is held up until the backoff has passed.
+    fn is_idle(&self) -> bool;
+    /// The number of user messages waiting in the cell's mailbox
+    fn queue_len(&self) -> usize;
+    /// The actor's decision on its failure. An actor that panics while deciding escalates.
+    fn on_failure(&self, failure: &Failure) -> Directive;
     fn uuid(&self) -> Uuid;
-    fn start(&self);
-    fn restart(&self);
+    fn scheduler(&self) -> Option<Arc<Scheduler>>;
+    /// Move the cell over to another scheduler, which must hold the claim on the cell
+    fn set_scheduler(&self, scheduler: Weak<Scheduler>);
+    /// Put the cell on its scheduler's run queue, unless it is already scheduled. Only the caller
+    /// that moves the cell from unscheduled to scheduled enqueues it, so the cell is never on
+    /// more than one run queue and is never run on two threads at once.
+    fn schedule(&self);
+    /// Mark the cell as no longer scheduled, once its scheduler is done running it
+    fn unschedule(&self);
+    /// Whether the cell has work its scheduler can do right now: system messages, or user
+    /// messages while the actor is running
+    fn has_work(&self) -> bool;
+    fn supervision(&self) -> &Supervision;
+    /// Register a watcher to be notified when this cell terminates. If the cell has already
+    /// terminated, the watcher is notified immediately.
+    fn watch(&self, watcher: Uuid, notify: Notify);
+    fn unwatch(&self, watcher: Uuid);
+    /// Notify all watchers that this cell has terminated and drop any watches it holds. Any
+    /// messages still in the mailbox are published as dead letters, and any pending system
+    /// messages are dropped.
+    fn terminate(&self);
+    /// Start the actor. The actor runs even if it fails to start, with the failure returned to
+    /// be handled like any other (see `supervision::handle_failure`).
+    fn start(&self) -> Result<(), Failure>;
+    /// Restart the actor, replacing it with a fresh instance either right away or once its
+    /// restart backoff has passed (see `recreate`). A panic of the old actor's `pre_restart` is
+    /// only logged, as the actor is being replaced anyway.
+    fn restart(&self, reason: RestartReason) -> Result<(), Failure>;
+    /// Replace a restarting actor with a fresh instance, returning the failure of the fresh
+    /// actor's constructor or `post_restart`
+    fn recreate(&self, reason: RestartReason) -> Result<(), Failure>;
+    /// Stop processing messages while the cell waits to be shut down
+    fn suspend(&self);
+    /// Halt the actor. A panic of its `pre_stop` is only logged, as the actor is stopping anyway.
     fn shutdown(&self);
+    /// Let the actor know that it has stopped for good, once the cell has terminated. A panic
+    /// of its `post_stop` is only logged.
+    fn post_stop(&self);
 }
 impl<A: Actor + 'static> ACell for Cell<A> {
-    fn process(&self) -> bool {
-        if let Some(f) = self.mailbox.try_recv() {
-            f();
-            return true;
+    fn process(&self, budget: usize) -> Batch {
+        let mut batch = Batch { handled: 0, failure: None };
+        if self.state() != actor::State::Running {
+            return batch;
+        }
+
+        let mut actor = self.actor.lock().unwrap();
+        while batch.handled < budget && !self.has_system_messages() {
+            match self.poll_task() {
+                Poll::Ready(Ok(())) => (),
+                Poll::Ready(Err(failure)) => {
+                    batch.failure = Some(failure);
+                    break;
+                }
+                Poll::Pending => break,
+            }
+            let envelope = match self.mailbox.pop() {
+                Some(envelope) => envelope,
+                None => break,
+            };
+            batch.handled += 1;
+            *self.last_message.lock().unwrap() = Some(envelope.message_type);
+            let context = self.context();
+            if let Err(failure) = catch_panic(self.uuid, || envelope.handle(&mut *actor, &context)) {
+                batch.failure = Some(failure);
+                break;
+            }
+        }
+        batch
+    }
+
+    fn tell(&self, msg: SystemMessage) {
+        // the queue is unlocked before waking the scheduler or answering a watch
+        let refused = match self.system.lock().unwrap().as_mut() {
+            Some(queue) => {
+                queue.push_back(msg);
+                None
+            }
+            None => Some(msg),
+        };
+        match refused {
+            None => self.wake(),
+            Some(SystemMessage::Watch(_, notify)) => notify(Terminated { id: self.uuid }),
+            Some(_) => (),
+        }
+    }
+
+    fn take_system_messages(&self) -> VecDeque<SystemMessage> {
+        self.system.lock().unwrap().as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    fn is_stopping(&self) -> bool {
+        self.state() == actor::State::Stopping
+    }
+
+    fn is_idle(&self) -> bool {
+        if self.has_system_messages() {
+            return false;
         }
-        false
+        self.backing_off.load(Ordering::SeqCst) || (self.mailbox.is_empty() && self.task.lock().unwrap().is_none())
+    }
+
+    fn queue_len(&self) -> usize {
+        self.mailbox.len()
+    }
+
+    fn on_failure(&self, failure: &Failure) -> Directive {
+        let actor = self.actor.lock().unwrap();
+        catch_panic(self.uuid, || Ok(actor.on_failure(failure))).unwrap_or(Directive::Escalate)
     }
 
     fn uuid(&self) -> Uuid {
-        self.uuid.clone()
+        self.uuid
+    }
+
+    fn scheduler(&self) -> Option<Arc<Scheduler>> {
+        Weak::upgrade(&self.parent_scheduler.lock().unwrap())
+    }
+
+    fn set_scheduler(&self, scheduler: Weak<Scheduler>) {
+        *self.parent_scheduler.lock().unwrap() = scheduler;
+    }
+
+    fn schedule(&self) {
+        if self.scheduled.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire).is_err() {
+            return;
+        }
+        let myself = Weak::upgrade(&self.myself.lock().unwrap());
+        match (myself, self.scheduler()) {
+            (Some(cell), Some(scheduler)) => scheduler.enqueue(cell),
+            _ => self.unschedule(),
+        }
+    }
+
+    fn unschedule(&self) {
+        self.scheduled.store(false, Ordering::Release);
     }
 
-    fn start(&self) {
-        self.actor.lock().unwrap().start();
-        self.actor_running_state.replace(actor::State::Running);
+    fn has_work(&self) -> bool {
+        let running = self.state() == actor::State::Running;
+        // the mailbox is held up while waiting on an async handler
+        let ready = match *self.task.lock().unwrap() {
+            Some(ref task) => task.is_woken(),
+            None => !self.mailbox.is_empty(),
+        };
+        self.has_system_messages() || (running && ready)
     }
 
-    fn restart(&self) {
-        self.shutdown();
-        {
+    fn supervision(&self) -> &Supervision {
+        &self.supervision
+    }
+
+    fn watch(&self, watcher: Uuid, notify: Notify) {
+        let mut watchers = self.watchers.lock().unwrap();
+        match watchers.as_mut() {
+            Some(watchers) => {
+                watchers.insert(watcher, notify);
+            }
+            None => notify(Terminated { id: self.uuid }),
+        }
+    }
+
+    fn unwatch(&self, watcher: Uuid) {
+        if let Some(watchers) = self.watchers.lock().unwrap().as_mut() {
+            watchers.remove(&watcher);
+        }
+    }
+
+    fn terminate(&self) {
+        // anything left in the stash or mailbox will never be processed
+        let stashed: Vec<_> = self.stash.lock().unwrap().drain(..).collect();
+        self.dead_letters(stashed, DeadLetterReason::Stopped);
+        self.dead_letters(self.mailbox.close(), DeadLetterReason::Stopped);
+
+        // watches that were still pending are answered along with the registered watchers
+        let pending = self.system.lock().unwrap().take().unwrap_or_default();
+        let watchers = self.watchers.lock().unwrap().take().unwrap_or_default();
+        let pending_watchers = pending.into_iter().filter_map(|msg| match msg {
+            SystemMessage::Watch(watcher, notify) => Some((watcher, notify)),
+            _ => None,
+        });
+        for (_, notify) in watchers.into_iter().chain(pending_watchers) {
+            notify(Terminated { id: self.uuid });
+        }
+
+        let watching: Vec<_> = self.watching.lock().unwrap().drain().collect();
+        for (_, watched) in watching {
+            if let Some(watched) = Weak::upgrade(&watched) {
+                watched.tell(SystemMessage::Unwatch(self.uuid));
+            }
+        }
+    }
+
+    fn start(&self) -> Result<(), Failure> {
+        let mut actor = self.actor.lock().unwrap();
+        let started = catch_panic(self.uuid, || {
+            actor.start();
+            Ok(actor.supervisor_strategy())
+        });
+        self.set_state(actor::State::Running);
+        self.supervision.set_strategy(started?);
+        Ok(())
+    }
+
+    fn restart(&self, reason: RestartReason) -> Result<(), Failure> {
+        // stashed messages are handled by the fresh actor, unless the mailbox is discarded
+        self.unstash_all();
+        self.release();
+        if self.mailbox_retention == MailboxRetention::Discard {
+            self.dead_letters(self.mailbox.drain(), DeadLetterReason::Discarded);
+        }
+
+        // an actor already waiting out its backoff has been told it is restarting
+        if !self.restart_pending.swap(true, Ordering::SeqCst) {
+            // the failed message is only of interest to an actor that failed itself
+            let last_message = match reason {
+                RestartReason::Failed(_) => self.last_message.lock().unwrap().take(),
+                _ => None,
+            };
+            self.set_state(actor::State::Restarting);
             let mut actor = self.actor.lock().unwrap();
-            *actor = (*self.actor_producer)();
+            let _ = catch_panic(self.uuid, || {
+                actor.pre_restart(&reason, last_message);
+                Ok(())
+            });
+        }
+
+        let delay = self.restarts.lock().unwrap().next_delay(&reason);
+        match delay {
+            Some(delay) => self.recreate_after(delay, reason),
+            None => self.recreate(reason),
         }
-        self.start();
+    }
+
+    fn recreate(&self, reason: RestartReason) -> Result<(), Failure> {
+        // the restart may have been overtaken by another one, or by the actor stopping
+        self.backing_off.store(false, Ordering::SeqCst);
+        if !self.restart_pending.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let mut actor = self.actor.lock().unwrap();
+        let restarted = catch_panic(self.uuid, || {
+            *actor = (*self.actor_producer)();
+            actor.post_restart(&reason);
+            Ok(actor.supervisor_strategy())
+        });
+        self.set_state(actor::State::Running);
+        self.supervision.set_strategy(restarted?);
+        Ok(())
+    }
+
+    fn suspend(&self) {
+        self.set_state(actor::State::Stopping);
     }
 
     fn shutdown(&self) {
-        self.actor_running_state.replace(actor::State::Stopping);
-        self.actor.lock().unwrap().pre_stop();
-        self.actor_running_state.replace(actor::State::Halted);
+        self.release();
+        self.backing_off.store(false, Ordering::SeqCst);
+        self.set_state(actor::State::Stopping);
+        // an actor stopped while waiting out its restart backoff already had `pre_restart`
+        if !self.restart_pending.swap(false, Ordering::SeqCst) {
+            let mut actor = self.actor.lock().unwrap();
+            let _ = catch_panic(self.uuid, || {
+                actor.pre_stop();
+                Ok(())
+            });
+        }
+        self.set_state(actor::State::Halted);
+    }
+
+    fn post_stop(&self) {
+        let mut actor = self.actor.lock().unwrap();
+        let _ = catch_panic(self.uuid, || {
+            actor.post_stop();
+            Ok(())
+        });
     }
 }
 
-impl PartialEq for ACell {
-    fn eq(&self, other: &ACell) -> bool {
+impl PartialEq for dyn ACell {
+    fn eq(&self, other: &dyn ACell) -> bool {
         self.uuid() == other.uuid()
     }
 }
-impl Eq for ACell {}
+impl Eq for dyn ACell {}
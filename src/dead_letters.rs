@@ -0,0 +1,99 @@
+use super::actor::{Actor, Receives};
+use super::address::{Address, SendError};
+
+use std::any::type_name;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use uuid::Uuid;
+
+/// DeadLetter describes a message that the system was unable to deliver, and dropped as a result.
+/// The message itself is gone by the time it is published, so only its type and intended
+/// recipient are reported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeadLetter {
+    /// The id of the actor the message was addressed to (see `Address::id`)
+    pub recipient: Uuid,
+    /// The type name of the undelivered message
+    pub message_type: &'static str,
+    pub reason: DeadLetterReason,
+}
+
+/// Why a message ended up as a `DeadLetter`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeadLetterReason {
+    /// The recipient had stopped, either before the message was sent or before it was processed
+    Stopped,
+    /// The recipient's mailbox was full (see `romeo::mailbox::Overflow`)
+    MailboxOverflow,
+    /// The recipient discarded its mailbox as it restarted (see `romeo::mailbox::MailboxRetention`)
+    Discarded,
+}
+
+/// Delivers a dead letter to a subscriber, returning false once the subscriber has stopped
+type Subscriber = (Uuid, Arc<dyn Fn(&DeadLetter) -> bool + Send + Sync>);
+
+/// DeadLetters is the system-wide sink for undeliverable messages. Every dead letter is counted
+/// and forwarded to each subscribed actor. It can be reached through `System::dead_letters`.
+///
+/// Note that messages handed back to the sender (such as by `Address::try_send`) are not
+/// considered dead letters, as they were never dropped. Dead letters about undelivered
+/// `DeadLetter`s are counted but not forwarded, as they would loop back to the subscribers.
+pub struct DeadLetters {
+    subscribers: Mutex<Vec<Subscriber>>,
+    count: AtomicUsize,
+}
+impl DeadLetters {
+    pub(crate) fn new() -> Self {
+        DeadLetters {
+            subscribers: Mutex::new(vec![]),
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Subscribe an actor to all dead letters published from now on. Subscribers are removed
+    /// automatically once they stop. Dead letters that don't fit a subscriber's full mailbox are
+    /// missed by that subscriber.
+    pub fn subscribe<A>(&self, subscriber: &Address<A>)
+    where
+        A: Actor + Receives<DeadLetter> + 'static,
+    {
+        let address = subscriber.clone();
+        let deliver = Arc::new(move |letter: &DeadLetter| {
+            !matches!(address.try_send(letter.clone()), Err(SendError::Stopped(_)))
+        });
+        self.subscribers.lock().unwrap().push((subscriber.id(), deliver));
+    }
+
+    pub fn unsubscribe<A: Actor + 'static>(&self, subscriber: &Address<A>) {
+        self.subscribers.lock().unwrap().retain(|(id, _)| *id != subscriber.id());
+    }
+
+    /// The total number of dead letters published since the system was created
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn publish(&self, letter: DeadLetter) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        debug!(
+            "Dead letter for actor {} ({:?}): {}",
+            letter.recipient, letter.reason, letter.message_type
+        );
+        if letter.message_type == type_name::<DeadLetter>() {
+            return;
+        }
+
+        // delivering may publish further dead letters (such as messages evicted from a
+        // subscriber's mailbox), so the subscribers are not locked meanwhile
+        let subscribers = self.subscribers.lock().unwrap().clone();
+        let stopped: Vec<Uuid> = subscribers
+            .iter()
+            .filter(|(_, deliver)| !deliver(&letter))
+            .map(|(id, _)| *id)
+            .collect();
+        if !stopped.is_empty() {
+            self.subscribers.lock().unwrap().retain(|(id, _)| !stopped.contains(id));
+        }
+    }
+}
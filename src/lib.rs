@@ -1,6 +1,9 @@
-#![feature(fnbox)]
 #![allow(dead_code)]
+// the value tables of the config docs align continued lines with the text of their item
+#![allow(clippy::doc_overindented_list_items)]
 
+extern crate core_affinity;
+#[macro_use]
 extern crate crossbeam_channel;
 #[macro_use]
 extern crate log;
@@ -10,10 +13,18 @@ extern crate uuid;
 
 pub mod actor;
 pub mod address;
+pub mod behavior;
 pub mod cell;
+pub mod dead_letters;
+pub mod mailbox;
+pub mod placement;
+pub mod runtime;
 pub mod scheduler;
+pub mod supervision;
 pub mod system;
+pub mod task;
+pub mod timer;
 
-pub use actor::{Actor, Receives};
+pub use actor::{Actor, Receives, ReceivesAsync};
 pub use address::Address;
 pub use system::System;
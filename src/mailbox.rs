@@ -0,0 +1,206 @@
+use super::dead_letters::DeadLetterReason;
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+
+/// Overflow is the policy applied when a message is sent to an actor whose (bounded) mailbox is
+/// already full. See `romeo::actor::ActorConfig::bounded_mailbox`.
+///
+/// Every message dropped or refused because of a full mailbox is counted (see
+/// `Address::overflow_count`), and messages that are dropped are published as dead letters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Block the sender until there is room in the mailbox. Only senders outside of the actor
+    /// system are ever blocked: a sender on a scheduler thread (such as an actor) could wait
+    /// forever on an actor that needs that very thread to make room, so the message is refused
+    /// as with `Reject` instead. `Address::try_send` never blocks either.
+    Block,
+    /// Drop the message being sent. Unlike with `Reject`, the message counts as sent, so
+    /// `Address::try_send` succeeds.
+    DropNewest,
+    /// Drop the oldest message in the mailbox to make room for the message being sent
+    DropOldest,
+    /// Refuse the message, handing it back to the sender through `Address::try_send`. As
+    /// `Address::send` has no way to hand the message back, it is dropped in that case.
+    Reject,
+}
+
+/// MailboxRetention decides what becomes of the messages waiting in an actor's mailbox when the
+/// actor is restarted. See `romeo::actor::ActorConfig::mailbox_on_restart`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MailboxRetention {
+    /// Keep the waiting messages (including stashed ones) for the fresh actor to handle
+    Keep,
+    /// Publish the waiting messages (including stashed ones) as dead letters, so that the fresh
+    /// actor starts with an empty mailbox. Messages sent while the actor waits out its restart
+    /// backoff are kept.
+    Discard,
+}
+
+/// The capacity and overflow policy of a mailbox
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct MailboxConfig {
+    capacity: Option<usize>,
+    overflow: Overflow,
+}
+impl MailboxConfig {
+    pub(crate) fn unbounded() -> Self {
+        MailboxConfig {
+            capacity: None,
+            overflow: Overflow::Block,
+        }
+    }
+
+    pub(crate) fn bounded(capacity: usize, overflow: Overflow) -> Self {
+        if capacity == 0 {
+            panic!("Bounded mailboxes must have a capacity of at least 1");
+        }
+        MailboxConfig {
+            capacity: Some(capacity),
+            overflow,
+        }
+    }
+}
+
+/// The reasons a message may not make it into a mailbox, handing back the message
+pub(crate) enum Rejected<M> {
+    /// The mailbox has been closed, as the actor has stopped
+    Closed(M),
+    /// The mailbox is full and the overflow policy refused the message
+    Full(M),
+    /// The mailbox is full and the overflow policy dropped the message, see `Overflow::DropNewest`
+    Dropped(M),
+}
+impl<M> Rejected<M> {
+    /// Why the message is a dead letter, should it be dropped
+    pub(crate) fn reason(&self) -> DeadLetterReason {
+        match self {
+            Rejected::Closed(_) => DeadLetterReason::Stopped,
+            Rejected::Full(_) | Rejected::Dropped(_) => DeadLetterReason::MailboxOverflow,
+        }
+    }
+}
+
+// ---
+// Mailbox
+// ---
+
+/// Mailbox is the queue of messages waiting to be processed by an actor. It can be bounded, in
+/// which case the configured `Overflow` policy is applied when it is full.
+pub(crate) struct Mailbox<T> {
+    state: Mutex<MailboxState<T>>,
+    /// Signalled when room is made in the mailbox, for senders blocked on a full mailbox
+    space: Condvar,
+    config: MailboxConfig,
+    overflows: AtomicUsize,
+}
+
+struct MailboxState<T> {
+    queue: VecDeque<T>,
+    closed: bool,
+}
+
+impl<T> Mailbox<T> {
+    pub(crate) fn new(config: MailboxConfig) -> Self {
+        Mailbox {
+            state: Mutex::new(MailboxState {
+                queue: VecDeque::new(),
+                closed: false,
+            }),
+            space: Condvar::new(),
+            config,
+            overflows: AtomicUsize::new(0),
+        }
+    }
+
+    /// Push a message into the mailbox. The message is only wrapped (with `wrap`) once it is
+    /// known to fit, so that a refused message can be handed back to the sender untouched.
+    ///
+    /// On success, any message evicted to make room (see `Overflow::DropOldest`) is returned.
+    pub(crate) fn push<M, F>(&self, msg: M, wrap: F, may_block: bool) -> Result<Option<T>, Rejected<M>>
+    where
+        F: FnOnce(M) -> T,
+    {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.closed {
+                return Err(Rejected::Closed(msg));
+            }
+
+            let full = self.config.capacity.is_some_and(|c| state.queue.len() >= c);
+            if !full {
+                state.queue.push_back(wrap(msg));
+                return Ok(None);
+            }
+
+            match self.config.overflow {
+                Overflow::Block if may_block => state = self.space.wait(state).unwrap(),
+                Overflow::DropNewest => {
+                    self.overflows.fetch_add(1, Ordering::SeqCst);
+                    return Err(Rejected::Dropped(msg));
+                }
+                Overflow::DropOldest => {
+                    self.overflows.fetch_add(1, Ordering::SeqCst);
+                    let evicted = state.queue.pop_front();
+                    state.queue.push_back(wrap(msg));
+                    return Ok(evicted);
+                }
+                _ => {
+                    self.overflows.fetch_add(1, Ordering::SeqCst);
+                    return Err(Rejected::Full(msg));
+                }
+            }
+        }
+    }
+
+    /// Put messages back at the front of the mailbox, in order, regardless of its capacity. The
+    /// messages are handed back if the mailbox has been closed.
+    pub(crate) fn push_front_all(&self, items: Vec<T>) -> Result<(), Vec<T>> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return Err(items);
+        }
+        for item in items.into_iter().rev() {
+            state.queue.push_front(item);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn pop(&self) -> Option<T> {
+        let item = self.state.lock().unwrap().queue.pop_front();
+        if item.is_some() {
+            self.space.notify_one();
+        }
+        item
+    }
+
+    /// Close the mailbox, refusing any further messages and waking any blocked senders. The
+    /// messages that were still waiting in the mailbox are returned.
+    pub(crate) fn close(&self) -> Vec<T> {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        self.space.notify_all();
+        state.queue.drain(..).collect()
+    }
+
+    /// Take every message waiting in the mailbox, waking any blocked senders
+    pub(crate) fn drain(&self) -> Vec<T> {
+        let mut state = self.state.lock().unwrap();
+        self.space.notify_all();
+        state.queue.drain(..).collect()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.state.lock().unwrap().queue.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.state.lock().unwrap().queue.is_empty()
+    }
+
+    /// The number of messages dropped or refused because the mailbox was full
+    pub(crate) fn overflows(&self) -> usize {
+        self.overflows.load(Ordering::SeqCst)
+    }
+}
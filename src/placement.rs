@@ -0,0 +1,151 @@
+use super::cell::ACell;
+use super::scheduler::Scheduler;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+
+use rand::{thread_rng, Rng};
+
+/// Placement decides which scheduler (and so which thread) a new actor starts out on. The
+/// system-wide strategy is set with `Config::placement` and can be overridden for a single actor
+/// with `ActorConfig::placement` (or `ActorConfig::colocate_with`).
+///
+/// Note that placement only decides where an actor starts. An idle scheduler may later steal the
+/// actor from a busy one.
+pub trait Placement: Send + Sync {
+    /// Choose one of the `request.schedulers` for the new actor, returning its index.
+    fn place(&self, request: &PlacementRequest) -> usize;
+}
+
+/// Everything a `Placement` strategy gets to know about a new actor and the schedulers it may be
+/// placed on.
+pub struct PlacementRequest<'a> {
+    pub schedulers: &'a [SchedulerInfo],
+    /// The index (within `schedulers`) of the scheduler the actor's parent lives on, if the actor
+    /// is being created as a child
+    pub parent: Option<usize>,
+    /// The placement key of the actor, see `ActorConfig::placement_key`
+    pub key: Option<u64>,
+}
+
+/// A read-only view of a scheduler, for making placement decisions
+pub struct SchedulerInfo {
+    scheduler: Arc<Scheduler>,
+}
+impl SchedulerInfo {
+    pub(crate) fn new(scheduler: Arc<Scheduler>) -> Self {
+        SchedulerInfo { scheduler }
+    }
+
+    pub(crate) fn scheduler(&self) -> &Arc<Scheduler> {
+        &self.scheduler
+    }
+
+    pub fn id(&self) -> usize {
+        self.scheduler.id()
+    }
+
+    /// The number of actors living on the scheduler
+    pub fn actor_count(&self) -> usize {
+        self.scheduler.actor_count()
+    }
+
+    /// The number of messages waiting in the mailboxes of the actors living on the scheduler
+    pub fn queue_depth(&self) -> usize {
+        self.scheduler.queue_depth()
+    }
+}
+
+// ---
+// Built-in strategies
+// ---
+
+/// Place actors on a uniformly random scheduler. This is the default strategy.
+pub struct Random;
+impl Placement for Random {
+    fn place(&self, request: &PlacementRequest) -> usize {
+        Rng::gen_range(&mut thread_rng(), 0, request.schedulers.len())
+    }
+}
+
+/// Place actors on each scheduler in turn
+pub struct RoundRobin {
+    next: AtomicUsize,
+}
+impl RoundRobin {
+    pub fn new() -> Self {
+        RoundRobin { next: AtomicUsize::new(0) }
+    }
+}
+impl Default for RoundRobin {
+    fn default() -> Self {
+        RoundRobin::new()
+    }
+}
+impl Placement for RoundRobin {
+    fn place(&self, request: &PlacementRequest) -> usize {
+        self.next.fetch_add(1, Ordering::Relaxed) % request.schedulers.len()
+    }
+}
+
+/// Place actors on the scheduler with the least load, measured either by the number of actors
+/// on the scheduler or by the number of messages waiting on it.
+pub struct LeastLoaded {
+    by_queue_depth: bool,
+}
+impl LeastLoaded {
+    pub fn by_actor_count() -> Self {
+        LeastLoaded { by_queue_depth: false }
+    }
+
+    pub fn by_queue_depth() -> Self {
+        LeastLoaded { by_queue_depth: true }
+    }
+}
+impl Placement for LeastLoaded {
+    fn place(&self, request: &PlacementRequest) -> usize {
+        let load = |s: &SchedulerInfo| if self.by_queue_depth { s.queue_depth() } else { s.actor_count() };
+        request.schedulers.iter().enumerate()
+            .min_by_key(|(_, s)| load(s))
+            .map_or(0, |(i, _)| i)
+    }
+}
+
+/// Place actors by their placement key (see `ActorConfig::placement_key`), so that actors with
+/// the same key share a scheduler. Actors without a key are placed randomly.
+pub struct HashOfKey;
+impl Placement for HashOfKey {
+    fn place(&self, request: &PlacementRequest) -> usize {
+        match request.key {
+            Some(key) => (key % request.schedulers.len() as u64) as usize,
+            None => Random.place(request),
+        }
+    }
+}
+
+/// Place child actors on the same scheduler as their parent. Top-level actors are placed
+/// randomly.
+pub struct CoLocateWithParent;
+impl Placement for CoLocateWithParent {
+    fn place(&self, request: &PlacementRequest) -> usize {
+        match request.parent {
+            Some(parent) => parent,
+            None => Random.place(request),
+        }
+    }
+}
+
+/// Place an actor on the scheduler of another actor, see `ActorConfig::colocate_with`. If the
+/// other actor has stopped, the actor is placed randomly.
+pub(crate) struct Alongside {
+    pub(crate) cell: Option<Weak<dyn ACell>>,
+}
+impl Placement for Alongside {
+    fn place(&self, request: &PlacementRequest) -> usize {
+        self.cell.as_ref()
+            .and_then(Weak::upgrade)
+            .and_then(|cell| cell.scheduler())
+            .and_then(|scheduler| request.schedulers.iter().position(|s| s.id() == scheduler.id()))
+            .unwrap_or_else(|| Random.place(request))
+    }
+}
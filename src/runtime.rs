@@ -0,0 +1,165 @@
+use super::actor::{Actor, ActorConfig, ActorConstructable, Props};
+use super::address::Address;
+use super::cell::{ACell, Cell};
+use super::dead_letters::DeadLetters;
+use super::placement::{Placement, PlacementRequest, Random, SchedulerInfo};
+use super::scheduler::Scheduler;
+use super::system::DEFAULT_DISPATCHER;
+use super::timer::TimerService;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock, Weak};
+
+/// The Runtime is the shared core of a running actor system: the set of schedulers (grouped into
+/// named dispatchers, see `Config::dispatcher`) and the means of placing new actors on them.
+/// `System` is a handle to the runtime for the outside world, while actors reach it (through
+/// their scheduler) to create children of their own.
+pub(crate) struct Runtime {
+    schedulers: RwLock<Vec<Arc<Scheduler>>>,
+    dead_letters: Arc<DeadLetters>,
+    timers: Arc<TimerService>,
+    /// The system-wide placement strategy for new actors, see `Config::placement`
+    placement: RwLock<Arc<dyn Placement>>,
+    /// Set once the system begins shutting down, after which new actors are no longer started
+    shutting_down: AtomicBool,
+    /// Set once a shutdown of the system has been requested, see `System::run`
+    shutdown_requested: Mutex<bool>,
+    shutdown_request: Condvar,
+}
+impl Runtime {
+    pub(crate) fn new() -> Self {
+        Runtime {
+            schedulers: RwLock::new(vec![]),
+            dead_letters: Arc::new(DeadLetters::new()),
+            timers: Arc::new(TimerService::new()),
+            placement: RwLock::new(Arc::new(Random)),
+            shutting_down: AtomicBool::new(false),
+            shutdown_requested: Mutex::new(false),
+            shutdown_request: Condvar::new(),
+        }
+    }
+
+    pub(crate) fn schedulers(&self) -> Vec<Arc<Scheduler>> {
+        self.schedulers.read().unwrap().clone()
+    }
+
+    /// The schedulers belonging to the named dispatcher
+    pub(crate) fn dispatcher(&self, name: &str) -> Vec<Arc<Scheduler>> {
+        let schedulers = self.schedulers.read().unwrap();
+        schedulers.iter().filter(|s| s.dispatcher() == name).cloned().collect()
+    }
+
+    /// Wake one parked scheduler of the same dispatcher as `busy`, so that it may steal work
+    /// from its busier siblings. See `Scheduler::steal`.
+    pub(crate) fn wake_idle_scheduler(&self, busy: &Scheduler) {
+        let schedulers = self.schedulers.read().unwrap();
+        let idle = schedulers.iter()
+            .filter(|s| s.id() != busy.id() && s.dispatcher() == busy.dispatcher())
+            .find(|s| s.unpark_if_parked());
+        if let Some(idle) = idle {
+            trace!("Woke idle scheduler {} to steal work", idle.id());
+        }
+    }
+
+    pub(crate) fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    /// Ask for the system to be shut down, see `System::shutdown_handle`
+    pub(crate) fn request_shutdown(&self) {
+        *self.shutdown_requested.lock().unwrap() = true;
+        self.shutdown_request.notify_all();
+    }
+
+    /// Block the current thread until a shutdown of the system has been requested
+    pub(crate) fn await_shutdown_request(&self) {
+        let mut requested = self.shutdown_requested.lock().unwrap();
+        while !*requested {
+            requested = self.shutdown_request.wait(requested).unwrap();
+        }
+    }
+
+    pub(crate) fn dead_letters(&self) -> Arc<DeadLetters> {
+        self.dead_letters.clone()
+    }
+
+    pub(crate) fn timers(&self) -> Arc<TimerService> {
+        self.timers.clone()
+    }
+
+    pub(crate) fn set_placement(&self, placement: Arc<dyn Placement>) {
+        *self.placement.write().unwrap() = placement;
+    }
+
+    pub(crate) fn add_scheduler(&self, scheduler: Arc<Scheduler>) {
+        self.schedulers.write().unwrap().push(scheduler);
+    }
+
+    /// Create a new actor on a scheduler of the actor's dispatcher, chosen by the actor's
+    /// placement strategy. If a `supervisor` is given, the new actor is registered as its child
+    /// and supervised by it.
+    ///
+    /// Actors created once the system is shutting down are never started, and any messages sent
+    /// to them are published as dead letters.
+    pub(crate) fn new_actor<A, P>(
+        &self,
+        props: P,
+        config: ActorConfig,
+        supervisor: Option<Weak<dyn ACell>>,
+    ) -> Address<A>
+    where
+        A: Actor + ActorConstructable<P> + 'static,
+        P: Props + 'static,
+    {
+        // Choose a scheduler for the Cell to live on
+        let scheduler = {
+            let dispatcher = config.dispatcher.as_ref().map_or(DEFAULT_DISPATCHER, |d| d.as_str());
+            let schedulers: Vec<SchedulerInfo> = self.dispatcher(dispatcher).into_iter().map(SchedulerInfo::new).collect();
+            if schedulers.is_empty() {
+                panic!("Cannot create actor, there is no dispatcher named `{}`", dispatcher);
+            }
+            let parent = supervisor.as_ref()
+                .and_then(Weak::upgrade)
+                .and_then(|parent| parent.scheduler())
+                .and_then(|parent| schedulers.iter().position(|s| s.id() == parent.id()));
+            let request = PlacementRequest {
+                schedulers: &schedulers,
+                parent,
+                key: config.placement_key,
+            };
+            let placement = match config.placement {
+                Some(ref placement) => placement.clone(),
+                None => self.placement.read().unwrap().clone(),
+            };
+            let scheduler_index = placement.place(&request) % schedulers.len();
+            schedulers[scheduler_index].scheduler().clone()
+        };
+
+        // Create the actor-cell
+        let producer = Box::new(move || A::new(&props));
+        let cell: Arc<Cell<A>> = Cell::new(
+            producer,
+            &config,
+            Arc::downgrade(&scheduler),
+            supervisor.clone(),
+            self.dead_letters.clone(),
+        );
+
+        if self.shutting_down.load(Ordering::SeqCst) {
+            warn!("Actor-cell {} created during shutdown, it will not be started", cell.uuid());
+            cell.terminate();
+            return Cell::address(cell);
+        }
+
+        // Link the cell into the actor hierarchy
+        if let Some(parent) = supervisor.as_ref().and_then(Weak::upgrade) {
+            parent.supervision().add_child(cell.clone());
+        }
+
+        // Hand the cell over to the scheduler
+        scheduler.register_new_cell(cell.clone());
+
+        // Return an address (handle to communicate with the actor in the cell)
+        Cell::address(cell)
+    }
+}
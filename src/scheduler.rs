@@ -1,75 +1,308 @@
-use super::cell::ACell;
+use super::cell::{ACell, SystemMessage};
+use super::runtime::Runtime;
+use super::supervision;
 
-use std::collections::VecDeque;
-use std::sync::{Arc, Mutex, RwLock};
-use std::{thread, time};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::thread;
 
 use uuid::Uuid;
 
+thread_local! {
+    /// Set while a scheduler runs a cell on this thread, see `Scheduler::run`
+    static RUNNING_ACTORS: RefCell<bool> = const { RefCell::new(false) };
+}
+
+/// Whether the current thread is running an actor for a scheduler. Sends from such a thread must
+/// never block on a full mailbox, as the actor they would wait on may need this very thread to
+/// make room (see `romeo::mailbox::Overflow::Block`).
+pub(crate) fn is_running_actors() -> bool {
+    RUNNING_ACTORS.with(|running| *running.borrow())
+}
+
 /// The Scheduler is responsible for scheduling execution of messages over actors for
 /// a single thread. It also keeps references to underlying cells in order to process
 /// messages and handle lifecycle events.
 ///
+/// Only cells with work to do are visited: a cell is put on its scheduler's run queue when a
+/// message (or system message) is sent to it, and stays there until the scheduler gets around
+/// to it. A cell is never on more than one run queue (see `Cell::schedule`), which ensures that
+/// the actor never runs on two threads at once.
+///
+/// Cells are not pinned to the scheduler they were created on. A scheduler that runs out of
+/// work steals runnable cells from its busier siblings (see `Scheduler::steal`).
+///
 /// `Scheduler` is meant to be used on a single thread and is itself not meant to be
 /// moved around threads. `Scheduler::start` will spawn a simple event-loop to process
 /// actor messages and lifecycle events in it's care.
 pub(crate) struct Scheduler {
     id: usize,
-    cells: RwLock<Vec<Arc<ACell>>>,
+    /// The name of the dispatcher (pool of schedulers) this scheduler belongs to. Cells are only
+    /// ever stolen between schedulers of the same dispatcher.
+    dispatcher: String,
+    cells: RwLock<HashMap<Uuid, Arc<dyn ACell>>>,
+    /// Cells with pending work, in the order they became runnable
+    run_queue: Mutex<VecDeque<Arc<dyn ACell>>>,
+    runtime: Weak<Runtime>,
+    /// The most messages a cell may process before the scheduler moves on to the next cell
+    throughput: usize,
 
-    // queues for various actions to be performed in the event-loop
-    actor_starts:   Mutex<VecDeque<Arc<ACell>>>,
-    actor_stops:    Mutex<VecDeque<Arc<ACell>>>,
-    actor_restarts: Mutex<VecDeque<Arc<ACell>>>,
+    /// The thread running the event-loop, which parks while there is no work to be done and is
+    /// woken whenever work is enqueued for one of its cells (see `Scheduler::wake`)
+    thread: Mutex<Option<thread::Thread>>,
+    /// Set while the event-loop is parked (or about to park) for lack of work
+    parked: AtomicBool,
+    shutdown: AtomicBool,
+    exited: AtomicBool,
 }
 impl Scheduler {
-    pub(crate) fn new(id: usize) -> Scheduler {
+    pub(crate) fn new(id: usize, dispatcher: &str, runtime: Weak<Runtime>, throughput: usize) -> Scheduler {
         Scheduler {
             id,
-            cells: RwLock::new(Vec::new()),
+            dispatcher: dispatcher.to_string(),
+            cells: RwLock::new(HashMap::new()),
+            run_queue: Mutex::new(VecDeque::new()),
+            runtime,
+            throughput,
 
-            actor_starts:   Mutex::new(VecDeque::new()),
-            actor_stops:    Mutex::new(VecDeque::new()),
-            actor_restarts: Mutex::new(VecDeque::new()),
+            thread: Mutex::new(None),
+            parked: AtomicBool::new(false),
+            shutdown: AtomicBool::new(false),
+            exited: AtomicBool::new(false),
         }
     }
 
-    pub(crate) fn register_new_cell(&self, cell: Arc<ACell>) {
-        let mut cells = self.cells.write().unwrap();
-        cells.push(cell.clone());
+    pub(crate) fn id(&self) -> usize {
+        self.id
+    }
 
-        let mut starts = self.actor_starts.lock().unwrap();
-        starts.push_back(cell);
+    pub(crate) fn dispatcher(&self) -> &str {
+        &self.dispatcher
     }
 
-    /// Stop an actor by removing it from the list of actors to process messages for and
-    /// adding it to the queue of actors to shutdown. This ensures that the actor shutdown
-    /// is processed on a scheduler as opposed to whatever context this function might
-    /// be called from.
-    ///
-    /// Note that removal of the actor from the scheduler happens within the schedulers
-    /// event loops (to avoid dead-locks).
-    pub(crate) fn stop_actor(&self, uuid: Uuid) {
-        let cells = self.cells.read().unwrap();
-        let cell = cells.iter().find(|c| c.uuid() == uuid);
-        cell.map(|c| {
-            self.actor_stops.lock().unwrap().push_back(c.clone())
-        });
-    }
-
-    /// Restart an actor by calling restart on the cell. For now defer this into the event
-    /// loop to avoid any conflicts with concurrently performing actions on the actor.
-    pub(crate) fn restart_actor(&self, uuid: Uuid) {
-        let cells = self.cells.read().unwrap();
-        let cell = cells.iter().find(|c| c.uuid() == uuid);
-        cell.map(|c| {
-            self.actor_restarts.lock().unwrap().push_back(c.clone());
-        });
+    pub(crate) fn runtime(&self) -> Option<Arc<Runtime>> {
+        Weak::upgrade(&self.runtime)
+    }
+
+    /// The ids of the actors still living on this scheduler
+    pub(crate) fn actor_ids(&self) -> Vec<Uuid> {
+        self.cells.read().unwrap().keys().cloned().collect()
+    }
+
+    pub(crate) fn actor_count(&self) -> usize {
+        self.cells.read().unwrap().len()
+    }
+
+    /// The number of user messages waiting across all cells on this scheduler
+    pub(crate) fn queue_depth(&self) -> usize {
+        self.cells.read().unwrap().values().map(|c| c.queue_len()).sum()
+    }
+
+    /// Whether every actor on this scheduler has worked through all of its messages
+    pub(crate) fn is_idle(&self) -> bool {
+        self.cells.read().unwrap().values().all(|c| c.is_idle())
+    }
+
+    /// Stop every top-level actor on this scheduler. Child actors are stopped by their parents.
+    pub(crate) fn stop_actors(&self) {
+        for cell in self.cells.read().unwrap().values() {
+            if cell.supervision().supervisor().is_none() {
+                cell.tell(SystemMessage::Stop);
+            }
+        }
+    }
+
+    /// Ask the event-loop to exit after its current tick. Any actors still living on the
+    /// scheduler are left as they are.
+    pub(crate) fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        self.wake();
+    }
+
+    /// Put a cell on the run queue, waking the event-loop. This must only be called by the one
+    /// caller that managed to schedule the cell, see `Cell::schedule`.
+    pub(crate) fn enqueue(&self, cell: Arc<dyn ACell>) {
+        self.run_queue.lock().unwrap().push_back(cell);
+        self.wake();
+    }
+
+    /// Wake the event-loop, as there is new work on this scheduler. Waking a scheduler that is
+    /// not parked makes it skip its next park, so no wakeup is ever lost.
+    pub(crate) fn wake(&self) {
+        if let Some(thread) = self.thread.lock().unwrap().as_ref() {
+            thread.unpark();
+        }
+    }
+
+    /// Wake the event-loop if it is parked, returning whether it was. Only one caller wakes a
+    /// parked scheduler, as the scheduler counts as unparked from then on.
+    pub(crate) fn unpark_if_parked(&self) -> bool {
+        let parked = self.parked.compare_exchange(true, false, Ordering::SeqCst, Ordering::SeqCst).is_ok();
+        if parked {
+            self.wake();
+        }
+        parked
+    }
+
+    /// Steal a runnable cell from the run queue of another scheduler of the same dispatcher,
+    /// returning whether one was stolen. Cells waiting on a run queue are not running, so they
+    /// can safely move over to this scheduler.
+    fn steal(&self) -> bool {
+        let schedulers = match self.runtime() {
+            Some(runtime) => runtime.dispatcher(&self.dispatcher),
+            None => return false,
+        };
+        let myself = match schedulers.iter().find(|s| s.id == self.id) {
+            Some(myself) => Arc::downgrade(myself),
+            None => return false,
+        };
+        for victim in schedulers.iter().filter(|s| s.id != self.id) {
+            // take from the back, leaving the victim the cells that have waited the longest
+            let stolen = victim.run_queue.lock().unwrap().pop_back();
+            if let Some(cell) = stolen {
+                debug!("Scheduler {} stole actor-cell {} from scheduler {}", self.id, cell.uuid(), victim.id);
+                victim.cells.write().unwrap().remove(&cell.uuid());
+                cell.set_scheduler(myself);
+                self.cells.write().unwrap().insert(cell.uuid(), cell.clone());
+                self.run_queue.lock().unwrap().push_back(cell);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Run the next cell on the run queue on the current thread, returning whether there was one.
+    /// This is how a system without threads is driven, see `System::start_manual`.
+    pub(crate) fn run_next(&self) -> bool {
+        let next = self.run_queue.lock().unwrap().pop_front();
+        match next {
+            Some(cell) => {
+                self.run(&cell);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the event-loop has exited, either after a `shutdown` or by panicking
+    pub(crate) fn has_exited(&self) -> bool {
+        self.exited.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn register_new_cell(&self, cell: Arc<dyn ACell>) {
+        self.cells.write().unwrap().insert(cell.uuid(), cell.clone());
+        cell.tell(SystemMessage::Start);
+    }
+
+    /// Run a cell taken from the run queue: handle its system messages, then a batch of its
+    /// messages. The cell is put back on the run queue if there is work left for it.
+    fn run(&self, cell: &Arc<dyn ACell>) {
+        let running = RUNNING_ACTORS.with(|running| running.replace(true));
+        self.run_cell(cell);
+        RUNNING_ACTORS.with(|flag| *flag.borrow_mut() = running);
+    }
+
+    fn run_cell(&self, cell: &Arc<dyn ACell>) {
+        // system messages always take priority over user messages. Those sent while handling
+        // them (such as by a restart that fails again) wait for the cell's next run, so that a
+        // cell cannot keep its scheduler busy on lifecycle events alone.
+        for msg in cell.take_system_messages() {
+            self.handle_system_message(cell, msg);
+        }
+
+        debug!("Processing messages for actor-cell: {}", cell.uuid());
+        let batch = cell.process(self.throughput);
+
+        // apply the failure policy of the actor if it failed while processing
+        if let Some(failure) = batch.failure {
+            supervision::handle_failure(cell.clone(), failure);
+        }
+
+        cell.unschedule();
+        if cell.has_work() {
+            cell.schedule();
+        }
+    }
+
+    /// Handle a system message for a cell living on this scheduler. This is only called from
+    /// the event loop, so that lifecycle events never happen concurrently with the actor
+    /// processing a message.
+    fn handle_system_message(&self, cell: &Arc<dyn ACell>, msg: SystemMessage) {
+        match msg {
+            SystemMessage::Start => {
+                debug!("Starting actor-cell: {}", cell.uuid());
+                if let Err(failure) = cell.start() {
+                    supervision::handle_failure(cell.clone(), failure);
+                }
+            }
+            SystemMessage::Restart(reason) => {
+                // a stopping actor is not brought back to life
+                if !cell.is_stopping() {
+                    debug!("Restarting actor-cell: {}", cell.uuid());
+                    if let Err(failure) = cell.restart(reason) {
+                        supervision::handle_failure(cell.clone(), failure);
+                    }
+                }
+            }
+            SystemMessage::Recreate(reason) => {
+                if !cell.is_stopping() {
+                    if let Err(failure) = cell.recreate(reason) {
+                        supervision::handle_failure(cell.clone(), failure);
+                    }
+                }
+            }
+            SystemMessage::Stop => {
+                if cell.is_stopping() {
+                    return;
+                }
+                // children are stopped before their parent, so hold off on the parent until
+                // each of its children has reported back as terminated
+                cell.suspend();
+                let children = cell.supervision().children();
+                if children.is_empty() {
+                    self.remove_cell(cell);
+                } else {
+                    debug!("Stopping children of actor-cell: {}", cell.uuid());
+                    children.into_iter().for_each(|child| child.tell(SystemMessage::Stop));
+                }
+            }
+            SystemMessage::Failed(failure) => {
+                if !cell.is_stopping() {
+                    supervision::handle_failure(cell.clone(), failure);
+                }
+            }
+            SystemMessage::ChildTerminated(child) => {
+                cell.supervision().remove_child(child);
+                if cell.is_stopping() && cell.supervision().children().is_empty() {
+                    self.remove_cell(cell);
+                }
+            }
+            SystemMessage::Watch(watcher, notify) => cell.watch(watcher, notify),
+            SystemMessage::Unwatch(watcher) => cell.unwatch(watcher),
+        }
+    }
+
+    /// Shut down a stopping cell and remove it from the scheduler, letting its supervisor (and
+    /// anyone watching) know that it has terminated.
+    fn remove_cell(&self, cell: &Arc<dyn ACell>) {
+        debug!("Shutting down actor-cell: {}", cell.uuid());
+        cell.shutdown();
+
+        self.cells.write().unwrap().remove(&cell.uuid());
+        debug!("Removed actor-cell from scheduler: {}", cell.uuid());
+
+        if let Some(supervisor) = cell.supervision().supervisor() {
+            supervisor.tell(SystemMessage::ChildTerminated(cell.uuid()));
+        }
+        cell.terminate();
+        cell.post_stop();
     }
 
     /// Start executes a simple event-loop on the current thread. The event loop is blocking and
-    /// will not exit on it's own. All actor messages and lifecycle events are processed within
-    /// this event loop.
+    /// will not exit until the scheduler is shut down (see `Scheduler::shutdown`). All actor
+    /// messages and lifecycle events are processed within this event loop.
     ///
     /// ## Notes for Future Self
     /// This event-loop implementation was not meant to be the end-all be-all of event-loops. It is
@@ -78,86 +311,46 @@ impl Scheduler {
     /// future to avoid spending too many cycles on this code. But alas, that was more to learn than
     /// I could handle when I implemented this.
     pub(crate) fn start(&self) {
-        let base_backoff_us = 2;
-        let max_backoff_us = 1_000_000;
-        let exp = 2;
+        // any work enqueued before the thread is known is picked up by the first tick
+        *self.thread.lock().unwrap() = Some(thread::current());
+        let _exit = ExitGuard(&self.exited);
 
-        let mut backoff_us = base_backoff_us;
-        loop {
+        while !self.shutdown.load(Ordering::SeqCst) {
             trace!("[Tick] Scheduler {}", self.id);
-            let mut zero_work_loop = true;
-            {
-                // stop an existing actor if one is available
-                {
-                    let mut stops = self.actor_stops.lock().unwrap();
-                    let mut cells = self.cells.write().unwrap();
-                    while let Some(actor) = stops.pop_front() {
-                        // call shutdown handle
-                        debug!("Shutting down actor-cell: {}", actor.uuid());
-                        actor.shutdown();
-
-                        // remove the cell from the scheduler
-                        let cell_lookup = cells.iter().enumerate()
-                            .find(|(_, c)| c.uuid() == actor.uuid())
-                            .map(|(i, c)| (i, c.uuid()));
-                        if let Some((id, uuid)) = cell_lookup {
-                            cells.remove(id);
-                            debug!("Removed actor-cell from scheduler: {}", uuid);
-                        }
-
-                        zero_work_loop = false;
-                    }
-                }
+            let (next, waiting) = {
+                let mut run_queue = self.run_queue.lock().unwrap();
+                (run_queue.pop_front(), run_queue.len())
+            };
 
-                // start a new actor if one is available
-                {
-                    let mut starts = self.actor_starts.lock().unwrap();
-                    while let Some(actor) = starts.pop_front() {
-                        debug!("Starting actor-cell: {}", actor.uuid());
-                        actor.start();
-                        zero_work_loop = false;
+            match next {
+                Some(cell) => {
+                    // with more cells waiting, an idle sibling may be able to take some of the work
+                    if waiting > 0 {
+                        if let Some(runtime) = self.runtime() {
+                            runtime.wake_idle_scheduler(self);
+                        }
                     }
+                    self.run(&cell);
                 }
-
-                // restart any actors that need to do so
-                {
-                    let mut restarts = self.actor_restarts.lock().unwrap();
-                    while let Some(actor) = restarts.pop_front() {
-                        debug!("Restarting actor-cell: {}", actor.uuid());
-                        actor.restart();
-                        zero_work_loop = false;
+                None => {
+                    if !self.shutdown.load(Ordering::SeqCst) && !self.steal() {
+                        // a wakeup between here and parking leaves the thread unparked, so it
+                        // isn't lost
+                        self.parked.store(true, Ordering::SeqCst);
+                        trace!("Parking scheduler {}", self.id);
+                        thread::park();
+                        self.parked.store(false, Ordering::SeqCst);
                     }
                 }
-
-
-                // handle all actors, processing a message if one is available
-                {
-                    let cells = self.cells.read().unwrap();
-                    cells.iter().for_each(|cell| {
-                        /* TODO: We need to check for a result type here and trigger a stop/restart/resume
-                         *       based on the error-handling/management policy set by the parent or guardian
-                         *       actor.
-                         */
-                        /* TODO: Currently we only process one message at a time for actors as a message
-                         *       might trigger the actor to stop/restart. It would be nice if we could
-                         *       provide a sentinal value to know to stop processing. So a process "up-to"
-                         *       rather than one at a time.
-                         */
-                        debug!("Processing message for actor-cell: {}", cell.uuid());
-                        if cell.process() {
-                            zero_work_loop = false;
-                        }
-                    });
-                }
-            }
-
-            if zero_work_loop {
-                thread::sleep(time::Duration::from_micros(backoff_us));
-                trace!("Sleeping scheduler {} for {}us", self.id, backoff_us);
-                backoff_us = (backoff_us * exp).min(max_backoff_us);
-            } else {
-                backoff_us = base_backoff_us;
             }
         }
     }
 }
+
+/// Marks the event-loop as exited once dropped, which also covers the loop panicking
+struct ExitGuard<'a>(&'a AtomicBool);
+impl<'a> Drop for ExitGuard<'a> {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,319 @@
+use super::actor::{Directive, Failure, RestartReason};
+use super::cell::{ACell, SystemMessage};
+
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use rand::{thread_rng, Rng};
+use uuid::Uuid;
+
+// ---
+// Supervisor Strategy
+// ---
+
+/// SupervisorStrategy describes how a parent actor reacts when one of its children fails and
+/// chooses to restart or stop (see `Actor::on_failure`). It is provided by the parent through
+/// `Actor::supervisor_strategy`.
+///
+/// Every strategy carries a restart limit: a child may be restarted at most `max_restarts` times
+/// within the `within` window. Once the limit is exceeded, the affected children are stopped and
+/// the parent itself fails with a `RestartLimitExceeded`, escalating the problem up the tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SupervisorStrategy {
+    kind: Strategy,
+    max_restarts: usize,
+    within: Duration,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+enum Strategy {
+    OneForOne,
+    AllForOne,
+    RestForOne,
+}
+
+impl SupervisorStrategy {
+    /// Only the failing child is restarted (or stopped).
+    pub fn one_for_one(max_restarts: usize, within: Duration) -> Self {
+        SupervisorStrategy { kind: Strategy::OneForOne, max_restarts, within }
+    }
+
+    /// All children of the parent are restarted (or stopped) when any one of them fails.
+    pub fn all_for_one(max_restarts: usize, within: Duration) -> Self {
+        SupervisorStrategy { kind: Strategy::AllForOne, max_restarts, within }
+    }
+
+    /// The failing child, along with every sibling created after it, is restarted (or stopped).
+    pub fn rest_for_one(max_restarts: usize, within: Duration) -> Self {
+        SupervisorStrategy { kind: Strategy::RestForOne, max_restarts, within }
+    }
+
+    /// Select the children affected by a failure of `failed`, given the children of the parent
+    /// in the order they were created.
+    fn scope(&self, children: Vec<Arc<dyn ACell>>, failed: &Arc<dyn ACell>) -> Vec<Arc<dyn ACell>> {
+        match self.kind {
+            Strategy::OneForOne => vec![failed.clone()],
+            Strategy::AllForOne => children,
+            Strategy::RestForOne => children
+                .into_iter()
+                .skip_while(|c| c.uuid() != failed.uuid())
+                .collect(),
+        }
+    }
+}
+
+/// The default strategy is one-for-one, allowing 10 restarts per child within a minute.
+impl Default for SupervisorStrategy {
+    fn default() -> Self {
+        SupervisorStrategy::one_for_one(10, Duration::from_secs(60))
+    }
+}
+
+/// The failure raised by a parent when one of its children exceeded the restart limit of
+/// the parent's `SupervisorStrategy`.
+#[derive(Debug)]
+pub struct RestartLimitExceeded {
+    pub child: Uuid,
+    pub max_restarts: usize,
+    pub within: Duration,
+}
+impl Display for RestartLimitExceeded {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "child actor {} exceeded {} restarts within {:?}",
+            self.child, self.max_restarts, self.within
+        )
+    }
+}
+impl Error for RestartLimitExceeded {}
+
+// ---
+// Restart Backoff
+// ---
+
+/// Backoff holds back the restarts of a failing actor, so that an actor failing against a
+/// dependency that is down does not spin in a tight restart loop. It is set per actor with
+/// `ActorConfig::restart_backoff`.
+///
+/// The actor waits `min` before its first restart, and twice as long before every further
+/// restart, up to `max`. Each wait is lengthened by a random share (up to the `jitter` factor)
+/// of itself, so that actors failing together don't restart in lock-step. Once the actor has
+/// run for `reset_after` without failing, the wait starts over at `min`.
+///
+/// Only restarts after a failure of the actor (or of a sibling, see `SupervisorStrategy`) are
+/// held back. While the actor waits, its mailbox is held up as well.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Backoff {
+    min: Duration,
+    max: Duration,
+    jitter: f64,
+    reset_after: Duration,
+}
+
+impl Backoff {
+    /// Back off exponentially from `min` to `max`, with a jitter of 0.2 and resetting after the
+    /// actor ran for `max` without failing.
+    pub fn exponential(min: Duration, max: Duration) -> Self {
+        Backoff { min, max, jitter: 0.2, reset_after: max }
+    }
+
+    /// Lengthen each wait by a random share of at most `factor` (between 0 and 1) of itself.
+    pub fn jitter(mut self, factor: f64) -> Self {
+        self.jitter = factor.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Start over at the shortest wait once the actor ran for `period` without failing.
+    pub fn reset_after(mut self, period: Duration) -> Self {
+        self.reset_after = period;
+        self
+    }
+
+    /// The wait before restart number `attempt` (counting from 0)
+    fn delay(&self, attempt: u32) -> Duration {
+        let wait = self.min.as_secs_f64() * 2f64.powi(attempt.min(64) as i32);
+        let wait = wait.min(self.max.as_secs_f64().max(self.min.as_secs_f64()));
+        Duration::from_secs_f64(wait * (1.0 + self.jitter * thread_rng().gen::<f64>()))
+    }
+}
+
+/// The restarts of a single actor, tracked to work out how long to hold back its next restart
+pub(crate) struct Restarts {
+    backoff: Option<Backoff>,
+    attempt: u32,
+    /// When the latest restart (including its backoff) completes
+    restarted: Option<Instant>,
+}
+impl Restarts {
+    pub(crate) fn new(backoff: Option<Backoff>) -> Self {
+        Restarts { backoff, attempt: 0, restarted: None }
+    }
+
+    /// Record a restart of the actor, returning how long to hold it back (if at all). Restarts
+    /// asked for by the actor itself (see `Context::restart`) are never held back.
+    pub(crate) fn next_delay(&mut self, reason: &RestartReason) -> Option<Duration> {
+        let backoff = match (self.backoff, reason) {
+            (Some(backoff), &RestartReason::Failed(_)) | (Some(backoff), &RestartReason::SiblingFailed(..)) => backoff,
+            _ => return None,
+        };
+        let now = Instant::now();
+        if self.restarted.is_some_and(|restarted| now >= restarted + backoff.reset_after) {
+            self.attempt = 0;
+        }
+        let delay = backoff.delay(self.attempt);
+        self.attempt = self.attempt.saturating_add(1);
+        self.restarted = Some(now + delay);
+        Some(delay)
+    }
+}
+
+// ---
+// Supervision, the per-cell bookkeeping of the actor hierarchy
+// ---
+pub(crate) struct Supervision {
+    supervisor: Option<Weak<dyn ACell>>,
+    /// Children in the order they were created (which `rest_for_one` relies on)
+    children: Mutex<Vec<Arc<dyn ACell>>>,
+    strategy: Mutex<SupervisorStrategy>,
+    restarts: Mutex<HashMap<Uuid, RestartWindow>>,
+}
+impl Supervision {
+    pub(crate) fn new(supervisor: Option<Weak<dyn ACell>>) -> Self {
+        Supervision {
+            supervisor,
+            children: Mutex::new(vec![]),
+            strategy: Mutex::new(SupervisorStrategy::default()),
+            restarts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn supervisor(&self) -> Option<Arc<dyn ACell>> {
+        self.supervisor.as_ref().and_then(Weak::upgrade)
+    }
+
+    pub(crate) fn children(&self) -> Vec<Arc<dyn ACell>> {
+        self.children.lock().unwrap().clone()
+    }
+
+    pub(crate) fn add_child(&self, child: Arc<dyn ACell>) {
+        self.children.lock().unwrap().push(child);
+    }
+
+    pub(crate) fn remove_child(&self, uuid: Uuid) {
+        self.children.lock().unwrap().retain(|c| c.uuid() != uuid);
+        self.restarts.lock().unwrap().remove(&uuid);
+    }
+
+    /// Cache the strategy of the supervising actor, which is read each time the actor starts
+    /// so that child failures don't require locking the (possibly busy) parent actor.
+    pub(crate) fn set_strategy(&self, strategy: SupervisorStrategy) {
+        *self.strategy.lock().unwrap() = strategy;
+    }
+
+    /// Record a restart of `child`, returning false if doing so exceeds the restart limit.
+    fn record_restart(&self, child: Uuid, strategy: &SupervisorStrategy) -> bool {
+        let mut restarts = self.restarts.lock().unwrap();
+        restarts
+            .entry(child)
+            .or_insert_with(RestartWindow::new)
+            .record(strategy.max_restarts, strategy.within)
+    }
+}
+
+/// Timestamps of the recent restarts of a single child, used to enforce restart limits.
+struct RestartWindow {
+    restarts: VecDeque<Instant>,
+}
+impl RestartWindow {
+    fn new() -> Self {
+        RestartWindow { restarts: VecDeque::new() }
+    }
+
+    fn record(&mut self, max_restarts: usize, within: Duration) -> bool {
+        let now = Instant::now();
+        while self.restarts.front().is_some_and(|t| now.duration_since(*t) > within) {
+            self.restarts.pop_front();
+        }
+        if self.restarts.len() >= max_restarts {
+            return false;
+        }
+        self.restarts.push_back(now);
+        true
+    }
+}
+
+// ---
+// Failure handling
+// ---
+
+/// Handle the failure of an actor. The actor chooses a `Directive` for itself, which is applied
+/// directly for top-level actors and through the `SupervisorStrategy` of the parent for children.
+///
+/// This must only be called by the scheduler of the failed actor. A failure handed up to the
+/// parent (when the child escalates, or exceeds its restart limit) is sent to the parent as a
+/// system message, so that the parent's own scheduler handles it in order.
+pub(crate) fn handle_failure(cell: Arc<dyn ACell>, failure: Failure) {
+    warn!("Actor-cell {} failed: {}", cell.uuid(), failure);
+    let directive = cell.on_failure(&failure);
+    match cell.supervision().supervisor() {
+        Some(supervisor) => supervise(supervisor, cell, directive, failure),
+        None => match directive {
+            Directive::Resume => debug!("Resuming actor-cell: {}", cell.uuid()),
+            Directive::Restart => restart(cell, RestartReason::Failed(Arc::new(failure))),
+            Directive::Stop => stop(cell),
+            Directive::Escalate => {
+                error!("Actor-cell {} escalated a failure but has no supervisor, stopping", cell.uuid());
+                stop(cell);
+            }
+        },
+    }
+}
+
+fn supervise(supervisor: Arc<dyn ACell>, child: Arc<dyn ACell>, directive: Directive, failure: Failure) {
+    let supervision = supervisor.supervision();
+    let strategy = *supervision.strategy.lock().unwrap();
+    match directive {
+        Directive::Resume => debug!("Resuming actor-cell: {}", child.uuid()),
+        Directive::Restart => {
+            let scope = strategy.scope(supervision.children(), &child);
+            if supervision.record_restart(child.uuid(), &strategy) {
+                let failure = Arc::new(failure);
+                for cell in scope {
+                    let reason = if cell.uuid() == child.uuid() {
+                        RestartReason::Failed(failure.clone())
+                    } else {
+                        RestartReason::SiblingFailed(child.uuid(), failure.clone())
+                    };
+                    restart(cell, reason);
+                }
+            } else {
+                scope.into_iter().for_each(stop);
+                let exceeded = RestartLimitExceeded {
+                    child: child.uuid(),
+                    max_restarts: strategy.max_restarts,
+                    within: strategy.within,
+                };
+                fail(supervisor, Box::new(exceeded));
+            }
+        }
+        Directive::Stop => strategy.scope(supervision.children(), &child).into_iter().for_each(stop),
+        Directive::Escalate => fail(supervisor, failure),
+    }
+}
+
+fn fail(cell: Arc<dyn ACell>, failure: Failure) {
+    cell.tell(SystemMessage::Failed(failure));
+}
+
+fn restart(cell: Arc<dyn ACell>, reason: RestartReason) {
+    cell.tell(SystemMessage::Restart(reason));
+}
+
+fn stop(cell: Arc<dyn ACell>) {
+    cell.tell(SystemMessage::Stop);
+}
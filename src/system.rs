@@ -1,35 +1,50 @@
-use super::actor::{Actor, ActorConstructable, Props};
+use super::actor::{Actor, ActorConfig, ActorConstructable, Props};
 use super::address::Address;
-use super::cell::Cell;
+use super::cell;
+use super::dead_letters::DeadLetters;
+use super::placement::{Placement, Random};
+use super::runtime::Runtime;
 use super::scheduler::Scheduler;
 
+use std::error::Error;
 use std::fmt::{self, Display, Formatter};
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
+use core_affinity::{self, CoreId};
 use num_cpus;
-use rand::{thread_rng, Rng, ThreadRng};
+use uuid::Uuid;
 
 /// System is the main handle into a running actor system. It is responsible for creating
 /// actors, starting the system (spawning threads and schedulers), stopping the system, etc.
 ///
 /// To configure the system, please refer to `romeo::system::Config`.
 pub struct System {
-    thread_handles: Vec<thread::JoinHandle<()>>,
-    thread_schedulers: Vec<Arc<Scheduler>>,
+    /// The threads of the schedulers, in order. The scheduler running on the caller's thread (see
+    /// `System::run`) has none.
+    thread_handles: Vec<Option<thread::JoinHandle<()>>>,
+    timer_handle: Option<thread::JoinHandle<()>>,
+    runtime: Arc<Runtime>,
     config: Config,
     state: RunningState,
-    rng: ThreadRng,
+    /// Set when the system runs on the caller's thread, see `System::start_manual`
+    manual: bool,
+    /// The scheduler that gets the next turn when stepping a manual system
+    next_scheduler: usize,
 }
 
 impl System {
     pub fn new() -> Self {
         System {
             thread_handles: vec![],
-            thread_schedulers: vec![],
+            timer_handle: None,
+            runtime: Arc::new(Runtime::new()),
             config: Config::default(),
             state: RunningState::AwaitingStart,
-            rng: thread_rng(),
+            manual: false,
+            next_scheduler: 0,
         }
     }
 
@@ -47,6 +62,15 @@ impl System {
     ///
     /// __Note:__ You the system must be running (see `spawn`) before you can create actors.
     pub fn new_actor<A, P>(&mut self, props: P) -> Address<A>
+    where
+        A: Actor + ActorConstructable<P> + 'static,
+        P: Props + 'static,
+    {
+        self.new_actor_with(props, ActorConfig::default())
+    }
+
+    /// Create a new actor (see `new_actor`) with the given actor configuration.
+    pub fn new_actor_with<A, P>(&mut self, props: P, config: ActorConfig) -> Address<A>
     where
         A: Actor + ActorConstructable<P> + 'static,
         P: Props + 'static,
@@ -59,51 +83,276 @@ impl System {
             );
         }
 
-        // Choose a scheduler for the Cell to live on
-        let scheduler_index = Rng::gen_range(&mut self.rng, 0, self.thread_schedulers.len());
-
-        // Create the actor-cell
-        let producer = Box::new(move || A::new(&props));
-        let cell: Arc<Cell<A>> = Cell::new(producer,
-                                           Arc::downgrade(&self.thread_schedulers[scheduler_index]));
-
-        // Hand the cell over to a random scheduler
-        self.thread_schedulers[scheduler_index].register_new_cell(cell.clone());
+        self.runtime.new_actor(props, config, None)
+    }
 
-        // Return an address (handle to communicate with the actor in the cell)
-        Cell::address(cell)
+    /// The system-wide sink for messages that could not be delivered. Subscribe to it in order
+    /// to observe (or count) lost messages.
+    pub fn dead_letters(&self) -> Arc<DeadLetters> {
+        self.runtime.dead_letters()
     }
 
     /// Spawns threads and creates schedulers (the runtime) in order to operate the actor system
     /// on top of. Before calling this method, ensure all desired configurations have been made
     /// via `System::with_config`. This method will not block the current thread.
     pub fn spawn(&mut self) {
+        self.start(false);
+    }
+
+    /// Run the system with the current thread as its only scheduler, blocking until the system
+    /// has been shut down. See `run_with`.
+    pub fn run<F: FnOnce(&mut System)>(self, init: F) -> Result<(), ShutdownError> {
+        self.run_with(1, init)
+    }
+
+    /// Run the system with `threads` schedulers for the default dispatcher, one of which runs on
+    /// the current thread, blocking until the system has been shut down. Other dispatchers (see
+    /// `Config::dispatcher`) still run on threads of their own.
+    ///
+    /// Once the system is running, `init` is called to create the first actors. The system runs
+    /// until a shutdown is requested, either by an actor (see `Context::shutdown_system`) or from
+    /// another thread (see `System::shutdown_handle`), after which it is shut down gracefully (see
+    /// `graceful_shutdown`).
+    pub fn run_with<F: FnOnce(&mut System)>(mut self, threads: usize, init: F) -> Result<(), ShutdownError> {
+        if threads == 0 {
+            panic!("System must run with at least 1 thread");
+        }
+        self.config.threads = threads;
+        let scheduler = self.start(true).expect("No scheduler left for the current thread");
+        init(&mut self);
+
+        // the shutdown is carried out by another thread, as this thread is needed to run the
+        // scheduler until the very end
+        let runtime = self.runtime.clone();
+        let hooks = self.config.hooks.clone();
+        let shutdown = thread::spawn(move || {
+            self.runtime.await_shutdown_request();
+            self.graceful_shutdown()
+        });
+
+        trace!("Running scheduler({}) on the current thread", scheduler.id());
+        let ran = panic::catch_unwind(AssertUnwindSafe(|| hooks.run(&scheduler)));
+        if ran.is_err() {
+            error!("Scheduler {} panicked, shutting down", scheduler.id());
+            runtime.request_shutdown();
+        }
+
+        let result = shutdown.join().expect("Graceful shutdown panicked");
+        if ran.is_ok() {
+            return result;
+        }
+        let mut err = match result {
+            Err(err) => err,
+            Ok(()) => ShutdownError {
+                unfinished_actors: vec![],
+                panicked_threads: vec![],
+                unjoined_threads: vec![],
+            },
+        };
+        err.panicked_threads.push(scheduler.id());
+        Err(err)
+    }
+
+    /// A handle for requesting a shutdown of a system that is running on the caller's thread
+    /// (see `System::run`), from any other thread.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Create the schedulers of every dispatcher and spawn their threads (see
+    /// `Config::thread_name_prefix`), along with the timer thread. If `on_caller` is set, the
+    /// first scheduler of the default dispatcher is given no thread and returned instead, for the
+    /// caller to run.
+    fn start(&mut self, on_caller: bool) -> Option<Arc<Scheduler>> {
+        self.state = RunningState::Starting;
+        self.runtime.set_placement(self.config.placement.clone());
+        if self.config.panic_backtraces {
+            cell::capture_panic_backtraces();
+        }
+        // Spawn threads and schedulers for each dispatcher
+        let mut caller = None;
+        let mut thread_id = 0;
+        for (dispatcher, threads) in self.config.dispatchers() {
+            for _ in 0..threads {
+                let scheduler = Arc::new(Scheduler::new(
+                    thread_id,
+                    &dispatcher,
+                    Arc::downgrade(&self.runtime),
+                    self.config.throughput,
+                ));
+                self.runtime.add_scheduler(scheduler.clone());
+                if on_caller && thread_id == 0 {
+                    caller = Some(scheduler);
+                    self.thread_handles.push(None);
+                } else {
+                    let mut builder = thread::Builder::new()
+                        .name(format!("{}-{}", self.config.thread_name_prefix, thread_id));
+                    if let Some(stack_size) = self.config.stack_size {
+                        builder = builder.stack_size(stack_size);
+                    }
+                    let hooks = self.config.hooks.clone();
+                    let handle = builder
+                        .spawn(move || {
+                            trace!("Spawning scheduler({}) on new thread", scheduler.id());
+                            hooks.run(&scheduler);
+                        })
+                        .expect("Failed to spawn scheduler thread");
+                    self.thread_handles.push(Some(handle));
+                }
+                thread_id += 1;
+            }
+        }
+
+        // Spawn the thread that fires timers
+        let timers = self.runtime.timers();
+        let handle = thread::Builder::new()
+            .name(format!("{}-timer", self.config.thread_name_prefix))
+            .spawn(move || timers.run())
+            .expect("Failed to spawn timer thread");
+        self.timer_handle = Some(handle);
+
+        self.state = RunningState::Running;
+        caller
+    }
+
+    /// Start the system without spawning any threads, for deterministic tests. Nothing happens
+    /// until the system is driven from the calling thread with `step` or `run_until_idle`.
+    ///
+    /// Each dispatcher gets a single scheduler (regardless of the configured number of threads),
+    /// so actors are always placed the same way, and an actor handles at most one message per
+    /// step. To get the reply to an `Address::ask`, step the system and then `Reply::poll`.
+    pub fn start_manual(&mut self) {
         self.state = RunningState::Starting;
-        // Spawn threads and schedulers
-        for thread_id in 0..self.config.threads {
-            let scheduler = Arc::new(Scheduler::new(thread_id));
-            self.thread_schedulers.push(scheduler.clone());
-            self.thread_handles.push(thread::spawn(move || {
-                trace!("Spawning scheduler({}) on new thread", thread_id);
-                scheduler.start();
-            }));
+        self.runtime.set_placement(self.config.placement.clone());
+        if self.config.panic_backtraces {
+            cell::capture_panic_backtraces();
+        }
+        for (id, (dispatcher, _)) in self.config.dispatchers().into_iter().enumerate() {
+            let scheduler = Scheduler::new(id, &dispatcher, Arc::downgrade(&self.runtime), 1);
+            self.runtime.add_scheduler(Arc::new(scheduler));
         }
+        self.manual = true;
         self.state = RunningState::Running;
-        // TODO: send all schedulers to each other (for reporting / work-stealing)
-    }
-
-    /// __Note:__ This currently does nothing but block on the scheduler threads, which will never
-    /// exit. This is to-be-implemented.
-    pub fn graceful_shutdown(self) {
-        // TODO: send some kind of shutdown flag
-        // Wait for all threads to stop
-        for handle in self.thread_handles {
-            // TODO: join returns a result, failing if the thread panic'ed
-            match handle.join() {
-                Ok(_) => (),
-                Err(_) => error!("Thread panicked during graceful shutdown while waiting to join"),
+    }
+
+    /// Process the next event of a system started with `start_manual`, on the calling thread:
+    /// the pending lifecycle events and next message of one actor. Timers that have come due are
+    /// fired first. Dispatchers take turns, in the order they were configured.
+    ///
+    /// Returns whether there was anything to process.
+    pub fn step(&mut self) -> bool {
+        if !self.manual {
+            panic!("Cannot step a system that was not started with `System::start_manual`");
+        }
+        self.runtime.timers().fire_due();
+
+        let schedulers = self.runtime.schedulers();
+        for offset in 0..schedulers.len() {
+            let index = (self.next_scheduler + offset) % schedulers.len();
+            if schedulers[index].run_next() {
+                self.next_scheduler = index + 1;
+                return true;
             }
         }
+        false
+    }
+
+    /// Step a system started with `start_manual` (see `step`) until every mailbox and lifecycle
+    /// queue is empty, returning the number of steps taken. Note that this never returns if
+    /// actors keep sending each other messages.
+    pub fn run_until_idle(&mut self) -> usize {
+        let mut steps = 0;
+        while self.step() {
+            steps += 1;
+        }
+        steps
+    }
+
+    /// Step a system started with `start_manual` until it is idle, or until `deadline` has passed
+    fn run_until_idle_or(&mut self, deadline: Instant) {
+        while Instant::now() < deadline && self.step() {}
+    }
+
+    /// Shut the system down, blocking the current thread until it has stopped. The shutdown
+    /// happens in the following steps:
+    ///
+    ///   1. The system stops accepting new actors, and all timers are cancelled
+    ///   2. Actors are given the chance to work through the messages left in their mailboxes,
+    ///      for up to the shutdown timeout (see `Config::shutdown_timeout`)
+    ///   3. All actors are stopped (children before their parents), calling `Actor::pre_stop`,
+    ///      for up to the shutdown timeout again. Actors that never drain their mailboxes thus
+    ///      still get the chance to stop.
+    ///   4. The schedulers are stopped and their threads joined
+    ///
+    /// If any actor had not stopped by the deadline, or any scheduler thread panicked or could
+    /// not be joined, a `ShutdownError` describing what went wrong is returned.
+    ///
+    /// A system started with `start_manual` is shut down by running it until idle (or until the
+    /// deadline) on the current thread, both before and after stopping the actors.
+    pub fn graceful_shutdown(mut self) -> Result<(), ShutdownError> {
+        self.state = RunningState::Stopping;
+        self.runtime.begin_shutdown();
+        self.runtime.timers().shutdown();
+        let schedulers = self.runtime.schedulers();
+
+        debug!("Draining mailboxes before shutdown");
+        let deadline = Instant::now() + self.config.shutdown_timeout;
+        if self.manual {
+            self.run_until_idle_or(deadline);
+        } else {
+            wait_until(deadline, || schedulers.iter().all(|s| s.is_idle()));
+        }
+
+        debug!("Stopping all actors");
+        let deadline = Instant::now() + self.config.shutdown_timeout;
+        schedulers.iter().for_each(|s| s.stop_actors());
+        if self.manual {
+            self.run_until_idle_or(deadline);
+        } else {
+            wait_until(deadline, || schedulers.iter().all(|s| s.actor_ids().is_empty()));
+        }
+        let unfinished_actors: Vec<Uuid> = schedulers.iter().flat_map(|s| s.actor_ids()).collect();
+
+        // Stop the schedulers. Well-behaved threads exit right away, so they are given a moment
+        // even if the deadline has already passed.
+        debug!("Stopping schedulers");
+        schedulers.iter().for_each(|s| s.shutdown());
+        if !self.manual {
+            let join_deadline = deadline.max(Instant::now() + Duration::from_millis(100));
+            wait_until(join_deadline, || schedulers.iter().all(|s| s.has_exited()));
+        }
+
+        let mut panicked_threads = vec![];
+        let mut unjoined_threads = vec![];
+        for (scheduler, handle) in schedulers.iter().zip(self.thread_handles.drain(..)) {
+            if !scheduler.has_exited() {
+                // joining would block forever, leave the thread be
+                unjoined_threads.push(scheduler.id());
+            } else if let Some(handle) = handle {
+                if handle.join().is_err() {
+                    error!("Scheduler {} panicked, found during graceful shutdown", scheduler.id());
+                    panicked_threads.push(scheduler.id());
+                }
+            }
+        }
+        if let Some(handle) = self.timer_handle.take() {
+            if handle.join().is_err() {
+                error!("Timer thread panicked, found during graceful shutdown");
+            }
+        }
+        self.state = RunningState::Stopped;
+
+        if unfinished_actors.is_empty() && panicked_threads.is_empty() && unjoined_threads.is_empty() {
+            Ok(())
+        } else {
+            Err(ShutdownError {
+                unfinished_actors,
+                panicked_threads,
+                unjoined_threads,
+            })
+        }
     }
 }
 
@@ -113,6 +362,12 @@ impl System {
 /// this behavior.
 unsafe impl Send for System {}
 
+impl Default for System {
+    fn default() -> Self {
+        System::new()
+    }
+}
+
 #[derive(PartialEq, Eq)]
 enum RunningState {
     AwaitingStart = 0,
@@ -139,21 +394,238 @@ impl Display for RunningState {
     }
 }
 
+/// ShutdownHandle requests a graceful shutdown of a system that is running on the caller's thread,
+/// see `System::run`.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    runtime: Arc<Runtime>,
+}
+impl ShutdownHandle {
+    /// Ask for the system to be shut down. This returns right away, while the shutdown happens
+    /// in the background.
+    pub fn shutdown(&self) {
+        self.runtime.request_shutdown();
+    }
+}
+
+unsafe impl Send for ShutdownHandle {}
+unsafe impl Sync for ShutdownHandle {}
+
+/// The name of the dispatcher actors run on unless configured otherwise, see `Config::dispatcher`
+pub const DEFAULT_DISPATCHER: &str = "default";
+
+/// Block the current thread until `done` holds or the deadline has passed
+fn wait_until<F: Fn() -> bool>(deadline: Instant, done: F) {
+    while !done() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// The problems found during `System::graceful_shutdown`. Schedulers are identified by the
+/// index of their thread.
+#[derive(Debug)]
+pub struct ShutdownError {
+    /// Actors that had not stopped by the shutdown deadline
+    pub unfinished_actors: Vec<Uuid>,
+    /// Schedulers whose thread panicked
+    pub panicked_threads: Vec<usize>,
+    /// Schedulers whose thread was still busy after the deadline, and was left running
+    pub unjoined_threads: Vec<usize>,
+}
+impl Display for ShutdownError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "shutdown incomplete: {} actor(s) unfinished, {} thread(s) panicked, {} thread(s) not joined",
+            self.unfinished_actors.len(),
+            self.panicked_threads.len(),
+            self.unjoined_threads.len()
+        )
+    }
+}
+impl Error for ShutdownError {}
+
 /// Config is the configuration for the `System` and can be specified with `System::with_config`.
 /// However, it is optional and a default will be provided if not specified manually.
 ///
 /// Values
 /// ------
-/// + `threads` - The number of threads the system should use. Defaults to the number of logical
-///               CPU cores reported by your system.
+/// + `threads` - The number of threads of the default dispatcher. Defaults to the number of
+///               logical CPU cores reported by your system.
+/// + `dispatchers` - Additional named pools of threads, which actors can be assigned to with
+///                   `ActorConfig::dispatcher`. Defaults to none. See `Config::dispatcher`.
+/// + `shutdown_timeout` - How long `System::graceful_shutdown` waits for actors to drain their
+///                        mailboxes, and then again for them to stop. Defaults to 5 seconds.
+/// + `placement` - The strategy used to choose the scheduler new actors are placed on (see
+///                 `romeo::placement`). Defaults to `placement::Random`.
+/// + `throughput` - The most messages an actor may process in one go before its scheduler
+///                  moves on to the next actor. Higher values trade fairness between actors for
+///                  less scheduling overhead. Defaults to 32.
+/// + `thread_name_prefix` - The prefix of the names of the scheduler threads, which are named
+///                          `<prefix>-<scheduler>`. Defaults to `romeo`.
+/// + `stack_size` - The stack size (in bytes) of the scheduler threads. Defaults to the
+///                  default stack size of the standard library.
+/// + `affinity` - The CPU core each scheduler thread is pinned to, if any. Defaults to none.
+///                See `Config::affinity`.
+/// + `on_thread_start` / `on_thread_stop` - Callbacks run on each scheduler thread as it starts
+///                                          and stops. Defaults to none.
+/// + `panic_backtraces` - Whether the panics of actors are logged with a backtrace, which takes
+///                        installing a process-wide panic hook. Defaults to true.
 pub struct Config {
     threads: usize,
+    dispatchers: Vec<(String, usize)>,
+    shutdown_timeout: Duration,
+    placement: Arc<dyn Placement>,
+    throughput: usize,
+    thread_name_prefix: String,
+    stack_size: Option<usize>,
+    hooks: ThreadHooks,
+    panic_backtraces: bool,
+}
+
+impl Config {
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Declare a named dispatcher: a pool of `threads` threads set aside for the actors assigned
+    /// to it. This keeps actors that block (on file or database I/O, for example) or hog the CPU
+    /// from stalling the actors of other dispatchers. A single-thread dispatcher pins all of its
+    /// actors to one thread.
+    ///
+    /// Declaring a dispatcher again replaces it, and declaring `DEFAULT_DISPATCHER` is the same
+    /// as setting `threads`.
+    pub fn dispatcher(mut self, name: &str, threads: usize) -> Self {
+        if threads == 0 {
+            panic!("Dispatcher `{}` must have at least 1 thread", name);
+        }
+        if name == DEFAULT_DISPATCHER {
+            self.threads = threads;
+        } else {
+            self.dispatchers.retain(|(n, _)| n != name);
+            self.dispatchers.push((name.to_string(), threads));
+        }
+        self
+    }
+
+    /// All dispatchers and their number of threads, starting with the default dispatcher
+    fn dispatchers(&self) -> Vec<(String, usize)> {
+        let default = (DEFAULT_DISPATCHER.to_string(), self.threads);
+        Some(default).into_iter().chain(self.dispatchers.iter().cloned()).collect()
+    }
+
+    pub fn shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    pub fn placement<P: Placement + 'static>(mut self, placement: P) -> Self {
+        self.placement = Arc::new(placement);
+        self
+    }
+
+    pub fn throughput(mut self, throughput: usize) -> Self {
+        if throughput == 0 {
+            panic!("Throughput must be at least 1 message");
+        }
+        self.throughput = throughput;
+        self
+    }
+
+    pub fn thread_name_prefix(mut self, prefix: &str) -> Self {
+        self.thread_name_prefix = prefix.to_string();
+        self
+    }
+
+    pub fn stack_size(mut self, bytes: usize) -> Self {
+        self.stack_size = Some(bytes);
+        self
+    }
+
+    /// Pin scheduler threads to CPU cores. `affinity` is given the index of each scheduler
+    /// (counting up across all dispatchers, starting with the default dispatcher) and returns
+    /// the id of the core to pin its thread to, or `None` to leave the thread unpinned. Pinning
+    /// to a core that does not exist logs a warning and leaves the thread unpinned.
+    pub fn affinity<F>(mut self, affinity: F) -> Self
+    where
+        F: Fn(usize) -> Option<usize> + Send + Sync + 'static,
+    {
+        self.hooks.affinity = Some(Arc::new(affinity));
+        self
+    }
+
+    /// Run a callback on each scheduler thread before it starts processing messages, such as to
+    /// set up thread-local state. The callback is given the index of the scheduler.
+    pub fn on_thread_start<F: Fn(usize) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.hooks.on_start = Some(Arc::new(callback));
+        self
+    }
+
+    /// Run a callback on each scheduler thread once its scheduler has stopped. The callback is
+    /// given the index of the scheduler. It is not run for threads that panicked.
+    pub fn on_thread_stop<F: Fn(usize) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.hooks.on_stop = Some(Arc::new(callback));
+        self
+    }
+
+    /// Log the panics of actors with a backtrace. This installs a panic hook for the whole
+    /// process when the system starts, which captures backtraces of actor code only and passes
+    /// every panic on to the hook installed before it. The hook is never uninstalled.
+    ///
+    /// Backtraces are captured by default. Turn this off to leave the process's panic hook alone.
+    pub fn panic_backtraces(mut self, capture: bool) -> Self {
+        self.panic_backtraces = capture;
+        self
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             threads: num_cpus::get(),
+            dispatchers: vec![],
+            shutdown_timeout: Duration::from_secs(5),
+            placement: Arc::new(Random),
+            throughput: 32,
+            thread_name_prefix: "romeo".to_string(),
+            stack_size: None,
+            hooks: ThreadHooks {
+                affinity: None,
+                on_start: None,
+                on_stop: None,
+            },
+            panic_backtraces: true,
+        }
+    }
+}
+
+/// The per-thread settings of `Config` that apply to every scheduler thread, including a
+/// scheduler run on the caller's thread (see `System::run`)
+#[derive(Clone)]
+struct ThreadHooks {
+    affinity: Option<Arc<dyn Fn(usize) -> Option<usize> + Send + Sync>>,
+    on_start: Option<Arc<dyn Fn(usize) + Send + Sync>>,
+    on_stop: Option<Arc<dyn Fn(usize) + Send + Sync>>,
+}
+impl ThreadHooks {
+    /// Run the event-loop of a scheduler on the current thread, see `Scheduler::start`
+    fn run(&self, scheduler: &Scheduler) {
+        let id = scheduler.id();
+        if let Some(core) = self.affinity.as_ref().and_then(|affinity| affinity(id)) {
+            let cores = core_affinity::get_core_ids().unwrap_or_default();
+            if cores.iter().any(|c| c.id == core) {
+                core_affinity::set_for_current(CoreId { id: core });
+            } else {
+                warn!("Cannot pin scheduler {} to core {}, as there is no such core", id, core);
+            }
+        }
+        if let Some(ref on_start) = self.on_start {
+            on_start(id);
+        }
+        scheduler.start();
+        if let Some(ref on_stop) = self.on_stop {
+            on_stop(id);
         }
     }
 }
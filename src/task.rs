@@ -0,0 +1,91 @@
+use super::actor::{Failure, Handling};
+use super::cell::ACell;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll, Wake, Waker};
+
+/// Task is the future of an async handler (see `romeo::actor::ReceivesAsync`) that an actor is
+/// waiting on. The future is polled by the actor's scheduler whenever it is woken, and the actor
+/// handles no further messages until it completes.
+pub(crate) struct Task {
+    future: Handling<()>,
+    waker: Arc<CellWaker>,
+}
+impl Task {
+    pub(crate) fn new(future: Handling<()>, cell: Weak<dyn ACell>) -> Self {
+        Task {
+            future,
+            // woken from the start, so that the future is polled for the first time
+            waker: Arc::new(CellWaker {
+                cell,
+                woken: AtomicBool::new(true),
+            }),
+        }
+    }
+
+    /// Whether the future has been woken since it was last polled
+    pub(crate) fn is_woken(&self) -> bool {
+        self.waker.woken.load(Ordering::SeqCst)
+    }
+
+    /// Poll the future, unless it has not been woken since it was last polled
+    pub(crate) fn poll(&mut self) -> Poll<Result<(), Failure>> {
+        if !self.waker.woken.swap(false, Ordering::SeqCst) {
+            return Poll::Pending;
+        }
+        let waker = Waker::from(self.waker.clone());
+        self.future.as_mut().poll(&mut Context::from_waker(&waker))
+    }
+}
+
+/// Wakes a task by putting its cell back on the cell's run queue
+struct CellWaker {
+    cell: Weak<dyn ACell>,
+    woken: AtomicBool,
+}
+impl Wake for CellWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::SeqCst);
+        if let Some(cell) = Weak::upgrade(&self.cell) {
+            cell.schedule();
+        }
+    }
+}
+
+/// Map is a future that applies a function to the output of another future once it completes,
+/// such as to reply to an `Address::ask_async`.
+pub(crate) struct Map<T, U> {
+    future: Pin<Box<dyn Future<Output = T> + Send>>,
+    map: Option<Box<dyn FnOnce(T) -> U + Send>>,
+}
+impl<T, U> Map<T, U> {
+    pub(crate) fn new<F>(future: Pin<Box<dyn Future<Output = T> + Send>>, map: F) -> Self
+    where
+        F: FnOnce(T) -> U + Send + 'static,
+    {
+        Map {
+            future,
+            map: Some(Box::new(map)),
+        }
+    }
+}
+impl<T, U> Future for Map<T, U> {
+    type Output = U;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<U> {
+        match self.future.as_mut().poll(cx) {
+            Poll::Ready(output) => {
+                let map = self.map.take().expect("Future polled after it completed");
+                Poll::Ready(map(output))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Timer is the handle to a message scheduled with `Context::schedule_once` or
+/// `Context::schedule_repeating`. Timers are cancelled automatically when the actor that
+/// scheduled them stops or restarts.
+#[derive(Clone)]
+pub struct Timer {
+    /// Held while the timer fires, so that cancelling waits for a message being sent
+    active: Arc<Mutex<bool>>,
+}
+impl Timer {
+    fn new(active: bool) -> Self {
+        Timer {
+            active: Arc::new(Mutex::new(active)),
+        }
+    }
+
+    /// Cancel the timer. No message is sent after this call returns (though a message that has
+    /// just been sent may still be waiting in the mailbox). If the timer is firing, this waits
+    /// for the message to be sent.
+    pub fn cancel(&self) {
+        *self.active.lock().unwrap() = false;
+    }
+
+    /// Whether the timer will still send a message. This is false once a timer has been
+    /// cancelled, or once a one-off timer has fired.
+    pub fn is_active(&self) -> bool {
+        *self.active.lock().unwrap()
+    }
+
+    /// Call `fire` unless the timer has been cancelled, returning whether it was called. The
+    /// check and the call happen under the same lock as `cancel`, so a cancelled timer never
+    /// fires.
+    fn fire_if_active(&self, fire: &mut dyn FnMut()) -> bool {
+        let active = self.active.lock().unwrap();
+        if *active {
+            fire();
+        }
+        *active
+    }
+}
+
+// ---
+// Timer Service
+// ---
+
+/// A timer waiting to fire
+struct Entry {
+    due: Instant,
+    /// Breaks ties between timers due at the same instant, in the order they were scheduled
+    seq: u64,
+    interval: Option<Duration>,
+    timer: Timer,
+    fire: Box<dyn FnMut() + Send>,
+}
+impl PartialEq for Entry {
+    fn eq(&self, other: &Entry) -> bool {
+        self.due == other.due && self.seq == other.seq
+    }
+}
+impl Eq for Entry {}
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Entry) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+/// Ordered so that the timer due first is at the top of the (max-)heap
+impl Ord for Entry {
+    fn cmp(&self, other: &Entry) -> Ordering {
+        (other.due, other.seq).cmp(&(self.due, self.seq))
+    }
+}
+
+struct State {
+    timers: BinaryHeap<Entry>,
+    next_seq: u64,
+    shutdown: bool,
+}
+
+/// The TimerService keeps track of all timers in the system, firing them from a thread of its
+/// own (see `TimerService::run`) so that waiting on timers never holds up a scheduler.
+pub(crate) struct TimerService {
+    state: Mutex<State>,
+    /// Signalled when a timer is scheduled, or the service is shut down
+    changed: Condvar,
+}
+impl TimerService {
+    pub(crate) fn new() -> Self {
+        TimerService {
+            state: Mutex::new(State {
+                timers: BinaryHeap::new(),
+                next_seq: 0,
+                shutdown: false,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Call `fire` once `delay` has passed, and then every `interval` (if given) until the timer
+    /// is cancelled. Timers scheduled after the service has shut down never fire.
+    pub(crate) fn schedule(&self, delay: Duration, interval: Option<Duration>, fire: Box<dyn FnMut() + Send>) -> Timer {
+        let mut state = self.state.lock().unwrap();
+        if state.shutdown {
+            return Timer::new(false);
+        }
+        let timer = Timer::new(true);
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.timers.push(Entry {
+            due: Instant::now() + delay,
+            seq,
+            interval,
+            timer: timer.clone(),
+            fire,
+        });
+        self.changed.notify_one();
+        timer
+    }
+
+    /// Stop firing timers and make `run` return. Pending timers are dropped.
+    pub(crate) fn shutdown(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.shutdown = true;
+        for entry in state.timers.drain() {
+            entry.timer.cancel();
+        }
+        self.changed.notify_one();
+    }
+
+    /// Fire timers as they come due, blocking the current thread until the service is shut down.
+    pub(crate) fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+        while !state.shutdown {
+            state = self.fire(state).0;
+            let now = Instant::now();
+            let next_due = state.timers.peek().map(|entry| entry.due);
+            state = match next_due {
+                _ if state.shutdown => state,
+                Some(due) if due > now => self.changed.wait_timeout(state, due - now).unwrap().0,
+                Some(_) => state,
+                None => self.changed.wait(state).unwrap(),
+            };
+        }
+    }
+
+    /// Fire the timers that are due without blocking, returning how many fired. This is how
+    /// timers are fired when the system is stepped manually, see `System::start_manual`.
+    pub(crate) fn fire_due(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        self.fire(state).1
+    }
+
+    /// Fire every timer that is due, handing back the lock along with how many fired
+    fn fire<'a>(&'a self, mut state: MutexGuard<'a, State>) -> (MutexGuard<'a, State>, usize) {
+        let mut fired = 0;
+        while !state.shutdown && state.timers.peek().is_some_and(|entry| entry.due <= Instant::now()) {
+            let mut entry = state.timers.pop().unwrap();
+
+            // fire without holding the lock, so that firing never holds up scheduling
+            drop(state);
+            let active = entry.timer.fire_if_active(&mut *entry.fire);
+            state = self.state.lock().unwrap();
+            if !active {
+                continue;
+            }
+            fired += 1;
+
+            match entry.interval {
+                Some(interval) if !state.shutdown => {
+                    // fixed-rate, so that a repeating timer doesn't drift
+                    entry.due += interval;
+                    state.timers.push(entry);
+                }
+                _ => entry.timer.cancel(),
+            }
+        }
+        (state, fired)
+    }
+}
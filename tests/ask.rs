@@ -0,0 +1,61 @@
+//! Request-response messaging with `Address::ask`, on a system running on threads of its own.
+extern crate romeo;
+
+mod common;
+
+use common::spawned_system;
+use romeo::actor::*;
+use romeo::address::AskError;
+use romeo::system::Config;
+use romeo::Address;
+
+use std::thread;
+use std::time::Duration;
+
+struct Echo;
+impl Actor for Echo {}
+
+struct EchoProps;
+impl Props for EchoProps {}
+impl ActorConstructable<EchoProps> for Echo {
+    fn new(_: &EchoProps) -> Self {
+        Echo
+    }
+}
+
+impl Receives<String> for Echo {
+    type Response = String;
+    fn receive(&mut self, msg: String, _: &Context<Self>) -> Result<String, Failure> {
+        Ok(msg)
+    }
+}
+
+/// Takes its time replying
+struct Sleep(Duration);
+impl Receives<Sleep> for Echo {
+    type Response = ();
+    fn receive(&mut self, msg: Sleep, _: &Context<Self>) -> Result<(), Failure> {
+        thread::sleep(msg.0);
+        Ok(())
+    }
+}
+
+#[test]
+fn ask_waits_for_the_reply() {
+    let mut system = spawned_system(Config::default().threads(2));
+    let echo: Address<Echo> = system.new_actor(EchoProps);
+
+    let reply = echo.ask("hello".to_string());
+    assert_eq!(reply.wait_timeout(Duration::from_secs(5)), Ok("hello".to_string()));
+    assert!(system.graceful_shutdown().is_ok());
+}
+
+#[test]
+fn ask_times_out_on_a_slow_actor() {
+    let mut system = spawned_system(Config::default().threads(1));
+    let echo: Address<Echo> = system.new_actor(EchoProps);
+
+    let reply = echo.ask(Sleep(Duration::from_millis(200)));
+    assert_eq!(reply.wait_timeout(Duration::from_millis(10)), Err(AskError::Timeout));
+    assert!(system.graceful_shutdown().is_ok());
+}
@@ -0,0 +1,159 @@
+//! Async handlers (see `ReceivesAsync`), whose futures are driven by the actor's scheduler.
+extern crate romeo;
+
+mod common;
+
+use common::{events, manual_system, new_log, Log};
+use romeo::actor::*;
+use romeo::address::AskError;
+use romeo::system::Config;
+use romeo::Address;
+
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context as TaskContext, Poll, Waker};
+
+/// A future that stays pending until the gate is opened from the test
+#[derive(Clone, Default)]
+struct Gate(Arc<Mutex<(bool, Option<Waker>)>>);
+impl Gate {
+    fn open(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.0 = true;
+        if let Some(waker) = state.1.take() {
+            waker.wake();
+        }
+    }
+}
+impl Future for Gate {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<()> {
+        let mut state = self.0.lock().unwrap();
+        if state.0 {
+            Poll::Ready(())
+        } else {
+            state.1 = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// Waits for a gate to open, then resolves to the outcome of `then`
+struct AfterGate {
+    gate: Gate,
+    then: Option<Box<dyn FnOnce() -> Result<(), Failure> + Send>>,
+}
+impl Future for AfterGate {
+    type Output = Result<(), Failure>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<Result<(), Failure>> {
+        match Pin::new(&mut self.gate).poll(cx) {
+            Poll::Ready(()) => Poll::Ready((self.then.take().unwrap())()),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+fn after_gate<F>(gate: Gate, then: F) -> Handling<()>
+where
+    F: FnOnce() -> Result<(), Failure> + Send + 'static,
+{
+    Box::pin(AfterGate { gate, then: Some(Box::new(then)) })
+}
+
+struct Worker {
+    log: Log,
+}
+impl Actor for Worker {}
+
+struct WorkerProps(Log);
+impl Props for WorkerProps {}
+impl ActorConstructable<WorkerProps> for Worker {
+    fn new(props: &WorkerProps) -> Self {
+        Worker { log: props.0.clone() }
+    }
+}
+
+struct Double(u32);
+impl ReceivesAsync<Double> for Worker {
+    type Response = u32;
+    fn receive_async(&mut self, msg: Double, _: &Context<Self>) -> Handling<u32> {
+        Box::pin(future::ready(Ok(msg.0 * 2)))
+    }
+}
+
+/// Wait for the gate to open
+struct Wait(Gate);
+impl ReceivesAsync<Wait> for Worker {
+    type Response = ();
+    fn receive_async(&mut self, msg: Wait, _: &Context<Self>) -> Handling<()> {
+        let log = self.log.clone();
+        after_gate(msg.0, move || {
+            common::record(&log, "gate opened".to_string());
+            Ok(())
+        })
+    }
+}
+
+/// Fail once the gate opens
+struct FailAfter(Gate);
+impl ReceivesAsync<FailAfter> for Worker {
+    type Response = ();
+    fn receive_async(&mut self, msg: FailAfter, _: &Context<Self>) -> Handling<()> {
+        after_gate(msg.0, || Err("broken".into()))
+    }
+}
+
+struct Note(u32);
+impl Receives<Note> for Worker {
+    type Response = ();
+    fn receive(&mut self, msg: Note, _: &Context<Self>) -> Result<(), Failure> {
+        common::record(&self.log, format!("note {}", msg.0));
+        Ok(())
+    }
+}
+
+#[test]
+fn ask_async_is_answered_once_the_future_completes() {
+    let mut system = manual_system(Config::default());
+    let worker: Address<Worker> = system.new_actor(WorkerProps(new_log()));
+
+    let reply = worker.ask_async(Double(21));
+    system.run_until_idle();
+    assert_eq!(reply.poll(), Ok(Some(42)));
+}
+
+#[test]
+fn the_mailbox_is_held_up_until_the_future_completes() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let worker: Address<Worker> = system.new_actor(WorkerProps(log.clone()));
+    let gate = Gate::default();
+
+    worker.send_async(Wait(gate.clone()));
+    worker.send(Note(1));
+    system.run_until_idle();
+    assert!(events(&log).is_empty());
+
+    gate.open();
+    system.run_until_idle();
+    assert_eq!(events(&log), vec!["gate opened", "note 1"]);
+}
+
+#[test]
+fn a_failing_future_fails_the_actor() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let worker: Address<Worker> = system.new_actor(WorkerProps(log.clone()));
+    let gate = Gate::default();
+
+    let failed = worker.ask_async(FailAfter(gate.clone()));
+    system.run_until_idle();
+    assert_eq!(failed.poll(), Ok(None));
+
+    gate.open();
+    let after = worker.ask_async(Double(1));
+    system.run_until_idle();
+    assert_eq!(failed.poll(), Err(AskError::Failed("broken".to_string())));
+    assert_eq!(after.poll(), Ok(Some(2)));
+}
@@ -0,0 +1,133 @@
+//! Actors switching behaviors at runtime, see `romeo::behavior::Behavior`.
+extern crate romeo;
+
+mod common;
+
+use common::manual_system;
+use romeo::actor::*;
+use romeo::behavior::Behavior;
+use romeo::system::{Config, System};
+use romeo::Address;
+
+struct Door;
+impl Actor for Door {}
+
+struct DoorProps;
+impl Props for DoorProps {}
+impl ActorConstructable<DoorProps> for Door {
+    fn new(_: &DoorProps) -> Self {
+        Door
+    }
+}
+
+struct Knock;
+impl Receives<Knock> for Door {
+    type Response = &'static str;
+    fn receive(&mut self, _: Knock, _: &Context<Self>) -> Result<&'static str, Failure> {
+        Ok("closed")
+    }
+}
+
+struct Peek;
+impl Receives<Peek> for Door {
+    type Response = &'static str;
+    fn receive(&mut self, _: Peek, _: &Context<Self>) -> Result<&'static str, Failure> {
+        Ok("a door")
+    }
+}
+
+/// Answer knocks with `answer` until told otherwise
+struct Push(&'static str);
+impl Receives<Push> for Door {
+    type Response = ();
+    fn receive(&mut self, msg: Push, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.push_behavior(answering(msg.0));
+        Ok(())
+    }
+}
+
+struct Become(&'static str);
+impl Receives<Become> for Door {
+    type Response = ();
+    fn receive(&mut self, msg: Become, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.become_behavior(answering(msg.0));
+        Ok(())
+    }
+}
+
+struct Unbecome;
+impl Receives<Unbecome> for Door {
+    type Response = ();
+    fn receive(&mut self, _: Unbecome, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.unbecome();
+        Ok(())
+    }
+}
+
+struct Fail;
+impl Receives<Fail> for Door {
+    type Response = ();
+    fn receive(&mut self, _: Fail, _: &Context<Self>) -> Result<(), Failure> {
+        Err("broken".into())
+    }
+}
+
+fn answering(answer: &'static str) -> Behavior<Door> {
+    Behavior::new().on(move |_: &mut Door, _: Knock, _: &Context<Door>| Ok(answer))
+}
+
+fn knock(system: &mut System, door: &Address<Door>) -> &'static str {
+    let reply = door.ask(Knock);
+    system.run_until_idle();
+    reply.poll().unwrap().unwrap()
+}
+
+#[test]
+fn a_behavior_replaces_the_handlers_of_its_messages_only() {
+    let mut system = manual_system(Config::default());
+    let door: Address<Door> = system.new_actor(DoorProps);
+    assert_eq!(knock(&mut system, &door), "closed");
+
+    door.send(Become("open"));
+    assert_eq!(knock(&mut system, &door), "open");
+    let peek = door.ask(Peek);
+    system.run_until_idle();
+    assert_eq!(peek.poll(), Ok(Some("a door")));
+}
+
+#[test]
+fn unbecome_returns_to_the_previous_behavior() {
+    let mut system = manual_system(Config::default());
+    let door: Address<Door> = system.new_actor(DoorProps);
+
+    door.send(Push("ajar"));
+    door.send(Push("open"));
+    assert_eq!(knock(&mut system, &door), "open");
+    door.send(Unbecome);
+    assert_eq!(knock(&mut system, &door), "ajar");
+    door.send(Unbecome);
+    assert_eq!(knock(&mut system, &door), "closed");
+    door.send(Unbecome);
+    assert_eq!(knock(&mut system, &door), "closed");
+}
+
+#[test]
+fn become_replaces_the_current_behavior() {
+    let mut system = manual_system(Config::default());
+    let door: Address<Door> = system.new_actor(DoorProps);
+
+    door.send(Push("ajar"));
+    door.send(Become("open"));
+    door.send(Unbecome);
+    assert_eq!(knock(&mut system, &door), "closed");
+}
+
+#[test]
+fn a_restarted_actor_starts_out_without_a_behavior() {
+    let mut system = manual_system(Config::default());
+    let door: Address<Door> = system.new_actor(DoorProps);
+
+    door.send(Push("open"));
+    door.send(Fail);
+    assert_eq!(knock(&mut system, &door), "closed");
+}
@@ -0,0 +1,52 @@
+//! Helpers shared by the integration tests. Not every test uses every helper.
+#![allow(dead_code)]
+
+use romeo::system::Config;
+use romeo::System;
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Events recorded by the actors of a test, in the order they happened
+pub type Log = Arc<Mutex<Vec<String>>>;
+
+pub fn new_log() -> Log {
+    Arc::new(Mutex::new(vec![]))
+}
+
+pub fn record(log: &Log, event: String) {
+    log.lock().unwrap().push(event);
+}
+
+pub fn events(log: &Log) -> Vec<String> {
+    log.lock().unwrap().clone()
+}
+
+/// A system that is stepped on the test's own thread, see `System::start_manual`
+pub fn manual_system(config: Config) -> System {
+    let mut system = System::new();
+    system.with_config(config);
+    system.start_manual();
+    system
+}
+
+/// A system running on threads of its own
+pub fn spawned_system(config: Config) -> System {
+    let mut system = System::new();
+    system.with_config(config);
+    system.spawn();
+    system
+}
+
+/// Wait (for up to a few seconds) until `done` holds, returning whether it did
+pub fn eventually<F: Fn() -> bool>(done: F) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !done() {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    true
+}
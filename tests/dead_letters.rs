@@ -0,0 +1,139 @@
+//! Dead letters, and `Address::try_send` handing back undeliverable messages.
+extern crate romeo;
+
+mod common;
+
+use common::manual_system;
+use romeo::actor::*;
+use romeo::address::SendError;
+use romeo::dead_letters::{DeadLetter, DeadLetterReason};
+use romeo::system::Config;
+use romeo::Address;
+
+use std::any::type_name;
+use std::sync::{Arc, Mutex};
+
+struct Target;
+impl Actor for Target {}
+
+struct TargetProps;
+impl Props for TargetProps {}
+impl ActorConstructable<TargetProps> for Target {
+    fn new(_: &TargetProps) -> Self {
+        Target
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Ping(u32);
+impl Receives<Ping> for Target {
+    type Response = ();
+    fn receive(&mut self, _: Ping, _: &Context<Self>) -> Result<(), Failure> {
+        Ok(())
+    }
+}
+
+struct Stop;
+impl Receives<Stop> for Target {
+    type Response = ();
+    fn receive(&mut self, _: Stop, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.stop();
+        Ok(())
+    }
+}
+
+/// Collects the dead letters it is sent
+struct Collector {
+    letters: Arc<Mutex<Vec<DeadLetter>>>,
+}
+impl Actor for Collector {}
+
+struct CollectorProps(Arc<Mutex<Vec<DeadLetter>>>);
+impl Props for CollectorProps {}
+impl ActorConstructable<CollectorProps> for Collector {
+    fn new(props: &CollectorProps) -> Self {
+        Collector { letters: props.0.clone() }
+    }
+}
+
+impl Receives<DeadLetter> for Collector {
+    type Response = ();
+    fn receive(&mut self, letter: DeadLetter, _: &Context<Self>) -> Result<(), Failure> {
+        self.letters.lock().unwrap().push(letter);
+        Ok(())
+    }
+}
+
+fn stopped(letter_for: &Address<Target>) -> DeadLetter {
+    DeadLetter {
+        recipient: letter_for.id(),
+        message_type: type_name::<Ping>(),
+        reason: DeadLetterReason::Stopped,
+    }
+}
+
+#[test]
+fn messages_to_a_stopped_actor_are_published_to_subscribers() {
+    let mut system = manual_system(Config::default());
+    let letters = Arc::new(Mutex::new(vec![]));
+    let collector: Address<Collector> = system.new_actor(CollectorProps(letters.clone()));
+    system.dead_letters().subscribe(&collector);
+    let target: Address<Target> = system.new_actor(TargetProps);
+    target.send(Stop);
+    system.run_until_idle();
+
+    target.send(Ping(1));
+    system.run_until_idle();
+
+    assert_eq!(*letters.lock().unwrap(), vec![stopped(&target)]);
+    assert_eq!(system.dead_letters().count(), 1);
+}
+
+#[test]
+fn messages_left_in_the_mailbox_of_a_stopping_actor_are_dead_letters() {
+    let mut system = manual_system(Config::default());
+    let letters = Arc::new(Mutex::new(vec![]));
+    let collector: Address<Collector> = system.new_actor(CollectorProps(letters.clone()));
+    system.dead_letters().subscribe(&collector);
+    let target: Address<Target> = system.new_actor(TargetProps);
+
+    target.send(Stop);
+    target.send(Ping(1));
+    target.send(Ping(2));
+    system.run_until_idle();
+
+    assert_eq!(*letters.lock().unwrap(), vec![stopped(&target), stopped(&target)]);
+}
+
+#[test]
+fn unsubscribed_actors_are_no_longer_sent_dead_letters() {
+    let mut system = manual_system(Config::default());
+    let letters = Arc::new(Mutex::new(vec![]));
+    let collector: Address<Collector> = system.new_actor(CollectorProps(letters.clone()));
+    system.dead_letters().subscribe(&collector);
+    system.dead_letters().unsubscribe(&collector);
+    let target: Address<Target> = system.new_actor(TargetProps);
+    target.send(Stop);
+    system.run_until_idle();
+
+    target.send(Ping(1));
+    system.run_until_idle();
+
+    assert!(letters.lock().unwrap().is_empty());
+    assert_eq!(system.dead_letters().count(), 1);
+}
+
+#[test]
+fn try_send_hands_back_a_message_for_a_stopped_actor() {
+    let mut system = manual_system(Config::default());
+    let target: Address<Target> = system.new_actor(TargetProps);
+    target.send(Stop);
+    system.run_until_idle();
+
+    match target.try_send(Ping(7)) {
+        Err(err @ SendError::Stopped(_)) => assert_eq!(err.into_inner(), Ping(7)),
+        other => panic!("Expected the message to be handed back, got {:?}", other),
+    }
+    // handed back rather than dropped, so not a dead letter
+    assert_eq!(system.dead_letters().count(), 0);
+}
@@ -0,0 +1,86 @@
+//! Named dispatchers: pools of threads set aside for some of the actors.
+extern crate romeo;
+
+mod common;
+
+use common::{manual_system, spawned_system};
+use romeo::actor::*;
+use romeo::system::Config;
+use romeo::Address;
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+struct Worker;
+impl Actor for Worker {}
+
+struct WorkerProps;
+impl Props for WorkerProps {}
+impl ActorConstructable<WorkerProps> for Worker {
+    fn new(_: &WorkerProps) -> Self {
+        Worker
+    }
+}
+
+/// The name of the thread the worker runs on
+struct Where;
+impl Receives<Where> for Worker {
+    type Response = String;
+    fn receive(&mut self, _: Where, _: &Context<Self>) -> Result<String, Failure> {
+        Ok(thread::current().name().unwrap_or_default().to_string())
+    }
+}
+
+/// Block the worker's thread until released, letting the test know once it is blocked
+struct Block(Sender<()>, Receiver<()>);
+impl Receives<Block> for Worker {
+    type Response = ();
+    fn receive(&mut self, msg: Block, _: &Context<Self>) -> Result<(), Failure> {
+        msg.0.send(()).unwrap();
+        msg.1.recv().unwrap();
+        Ok(())
+    }
+}
+
+fn where_is(worker: &Address<Worker>) -> String {
+    worker.ask(Where).wait_timeout(Duration::from_secs(5)).unwrap()
+}
+
+#[test]
+fn actors_run_on_the_threads_of_their_dispatcher() {
+    let config = Config::default().threads(2).dispatcher("blocking", 2).thread_name_prefix("pool");
+    let mut system = spawned_system(config);
+
+    for _ in 0..4 {
+        let default: Address<Worker> = system.new_actor(WorkerProps);
+        let blocking: Address<Worker> =
+            system.new_actor_with(WorkerProps, ActorConfig::default().dispatcher("blocking"));
+        assert!(["pool-0", "pool-1"].contains(&where_is(&default).as_str()));
+        assert!(["pool-2", "pool-3"].contains(&where_is(&blocking).as_str()));
+    }
+    assert!(system.graceful_shutdown().is_ok());
+}
+
+#[test]
+fn a_blocked_dispatcher_does_not_stall_the_others() {
+    let mut system = spawned_system(Config::default().threads(1).dispatcher("blocking", 1));
+    let blocked: Address<Worker> = system.new_actor_with(WorkerProps, ActorConfig::default().dispatcher("blocking"));
+    let other: Address<Worker> = system.new_actor(WorkerProps);
+
+    let (is_blocked, blocked_on) = channel();
+    let (release, released) = channel();
+    blocked.send(Block(is_blocked, released));
+    blocked_on.recv().unwrap();
+
+    where_is(&other);
+    release.send(()).unwrap();
+    assert!(system.graceful_shutdown().is_ok());
+}
+
+#[test]
+#[should_panic(expected = "there is no dispatcher named `missing`")]
+fn creating_an_actor_on_a_missing_dispatcher_panics() {
+    let mut system = manual_system(Config::default());
+    let _: Address<Worker> = system.new_actor_with(WorkerProps, ActorConfig::default().dispatcher("missing"));
+}
@@ -0,0 +1,172 @@
+//! The lifecycle hooks of `Actor`, around restarts and stops.
+extern crate romeo;
+
+mod common;
+
+use common::{events, manual_system, new_log, Log};
+use romeo::actor::*;
+use romeo::system::Config;
+use romeo::Address;
+
+use std::any::type_name;
+
+/// Records every lifecycle hook
+struct Hooked {
+    log: Log,
+}
+impl Actor for Hooked {
+    fn start(&mut self) {
+        common::record(&self.log, "start".to_string());
+    }
+
+    fn pre_stop(&mut self) {
+        common::record(&self.log, "pre_stop".to_string());
+    }
+
+    fn post_stop(&mut self) {
+        common::record(&self.log, "post_stop".to_string());
+    }
+
+    fn pre_restart(&mut self, reason: &RestartReason, last_message: Option<&'static str>) {
+        common::record(&self.log, format!("pre_restart {} {:?}", describe(reason), last_message));
+    }
+
+    fn post_restart(&mut self, reason: &RestartReason) {
+        common::record(&self.log, format!("post_restart {}", describe(reason)));
+    }
+}
+
+fn describe(reason: &RestartReason) -> String {
+    match reason {
+        RestartReason::Failed(failure) => format!("failed: {}", failure),
+        RestartReason::SiblingFailed(_, failure) => format!("sibling failed: {}", failure),
+        RestartReason::Requested => "requested".to_string(),
+    }
+}
+
+/// Leaves every lifecycle hook but `start`, `pre_stop` and `post_stop` to its default
+struct Defaulted {
+    log: Log,
+}
+impl Actor for Defaulted {
+    fn start(&mut self) {
+        common::record(&self.log, "start".to_string());
+    }
+
+    fn pre_stop(&mut self) {
+        common::record(&self.log, "pre_stop".to_string());
+    }
+
+    fn post_stop(&mut self) {
+        common::record(&self.log, "post_stop".to_string());
+    }
+}
+
+struct LogProps(Log);
+impl Props for LogProps {}
+impl ActorConstructable<LogProps> for Hooked {
+    fn new(props: &LogProps) -> Self {
+        Hooked { log: props.0.clone() }
+    }
+}
+impl ActorConstructable<LogProps> for Defaulted {
+    fn new(props: &LogProps) -> Self {
+        Defaulted { log: props.0.clone() }
+    }
+}
+
+struct Fail;
+impl Receives<Fail> for Hooked {
+    type Response = ();
+    fn receive(&mut self, _: Fail, _: &Context<Self>) -> Result<(), Failure> {
+        Err("broken".into())
+    }
+}
+impl Receives<Fail> for Defaulted {
+    type Response = ();
+    fn receive(&mut self, _: Fail, _: &Context<Self>) -> Result<(), Failure> {
+        Err("broken".into())
+    }
+}
+
+struct Restart;
+impl Receives<Restart> for Hooked {
+    type Response = ();
+    fn receive(&mut self, _: Restart, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.restart();
+        Ok(())
+    }
+}
+
+struct Stop;
+impl Receives<Stop> for Hooked {
+    type Response = ();
+    fn receive(&mut self, _: Stop, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.stop();
+        Ok(())
+    }
+}
+impl Receives<Stop> for Defaulted {
+    type Response = ();
+    fn receive(&mut self, _: Stop, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.stop();
+        Ok(())
+    }
+}
+
+#[test]
+fn a_failed_actor_is_told_why_and_on_which_message_it_is_restarted() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let hooked: Address<Hooked> = system.new_actor(LogProps(log.clone()));
+
+    hooked.send(Fail);
+    system.run_until_idle();
+    assert_eq!(
+        events(&log),
+        vec![
+            "start".to_string(),
+            format!("pre_restart failed: broken Some({:?})", type_name::<Fail>()),
+            "post_restart failed: broken".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn a_requested_restart_has_no_failed_message() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let hooked: Address<Hooked> = system.new_actor(LogProps(log.clone()));
+
+    hooked.send(Restart);
+    system.run_until_idle();
+    assert_eq!(events(&log), vec!["start", "pre_restart requested None", "post_restart requested"]);
+}
+
+#[test]
+fn post_stop_is_called_once_the_actor_stops_for_good() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let hooked: Address<Hooked> = system.new_actor(LogProps(log.clone()));
+
+    hooked.send(Restart);
+    hooked.send(Stop);
+    hooked.send(Stop);
+    system.run_until_idle();
+    assert_eq!(
+        events(&log),
+        vec!["start", "pre_restart requested None", "post_restart requested", "pre_stop", "post_stop"]
+    );
+}
+
+#[test]
+fn restart_hooks_default_to_the_stop_and_start_hooks() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let defaulted: Address<Defaulted> = system.new_actor(LogProps(log.clone()));
+
+    defaulted.send(Fail);
+    defaulted.send(Stop);
+    system.run_until_idle();
+    assert_eq!(events(&log), vec!["start", "pre_stop", "start", "pre_stop", "post_stop"]);
+}
@@ -0,0 +1,149 @@
+//! Bounded mailboxes and the overflow policies applied once they are full.
+extern crate romeo;
+
+mod common;
+
+use common::{events, manual_system, new_log, spawned_system, Log};
+use romeo::actor::*;
+use romeo::address::SendError;
+use romeo::mailbox::Overflow;
+use romeo::system::Config;
+use romeo::{Address, System};
+
+use std::thread;
+
+struct Recorder {
+    log: Log,
+}
+impl Actor for Recorder {}
+
+struct RecorderProps(Log);
+impl Props for RecorderProps {}
+impl ActorConstructable<RecorderProps> for Recorder {
+    fn new(props: &RecorderProps) -> Self {
+        Recorder { log: props.0.clone() }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Note(u32);
+impl Receives<Note> for Recorder {
+    type Response = ();
+    fn receive(&mut self, msg: Note, _: &Context<Self>) -> Result<(), Failure> {
+        common::record(&self.log, format!("note {}", msg.0));
+        Ok(())
+    }
+}
+
+/// Sends the given notes on to another recorder, from within the actor system
+struct Forward(Address<Recorder>, Vec<u32>);
+impl Receives<Forward> for Recorder {
+    type Response = ();
+    fn receive(&mut self, msg: Forward, _: &Context<Self>) -> Result<(), Failure> {
+        for note in msg.1 {
+            msg.0.send(Note(note));
+        }
+        Ok(())
+    }
+}
+
+fn bounded(system: &mut System, capacity: usize, overflow: Overflow, log: &Log) -> Address<Recorder> {
+    system.new_actor_with(
+        RecorderProps(log.clone()),
+        ActorConfig::default().bounded_mailbox(capacity, overflow),
+    )
+}
+
+fn notes(numbers: &[u32]) -> Vec<String> {
+    numbers.iter().map(|n| format!("note {}", n)).collect()
+}
+
+#[test]
+fn drop_newest_accepts_and_drops_the_message() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let recorder = bounded(&mut system, 2, Overflow::DropNewest, &log);
+
+    assert!(recorder.try_send(Note(1)).is_ok());
+    assert!(recorder.try_send(Note(2)).is_ok());
+    assert!(recorder.try_send(Note(3)).is_ok());
+    assert_eq!(recorder.overflow_count(), 1);
+    assert_eq!(system.dead_letters().count(), 1);
+
+    system.run_until_idle();
+    assert_eq!(events(&log), notes(&[1, 2]));
+}
+
+#[test]
+fn drop_oldest_keeps_the_newest_messages() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let recorder = bounded(&mut system, 2, Overflow::DropOldest, &log);
+
+    for note in 1..5 {
+        assert!(recorder.try_send(Note(note)).is_ok());
+    }
+    assert_eq!(recorder.overflow_count(), 2);
+    assert_eq!(system.dead_letters().count(), 2);
+
+    system.run_until_idle();
+    assert_eq!(events(&log), notes(&[3, 4]));
+}
+
+#[test]
+fn reject_hands_the_message_back() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let recorder = bounded(&mut system, 1, Overflow::Reject, &log);
+
+    assert!(recorder.try_send(Note(1)).is_ok());
+    match recorder.try_send(Note(2)) {
+        Err(err @ SendError::Full(_)) => assert_eq!(err.into_inner(), Note(2)),
+        _ => panic!("expected the mailbox to be full"),
+    }
+    recorder.send(Note(3));
+    assert_eq!(recorder.overflow_count(), 2);
+    assert_eq!(system.dead_letters().count(), 1);
+
+    system.run_until_idle();
+    assert_eq!(events(&log), notes(&[1]));
+}
+
+#[test]
+fn block_never_blocks_a_sender_on_a_scheduler_thread() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let sender: Address<Recorder> = system.new_actor(RecorderProps(new_log()));
+    let recorder = bounded(&mut system, 1, Overflow::Block, &log);
+
+    // in manual mode the recorder can only make room on this very thread
+    sender.send(Forward(recorder.clone(), vec![1, 2]));
+    system.run_until_idle();
+
+    assert_eq!(events(&log), notes(&[1]));
+    assert_eq!(recorder.overflow_count(), 1);
+    assert_eq!(system.dead_letters().count(), 1);
+}
+
+#[test]
+fn block_holds_up_senders_outside_of_the_system_until_there_is_room() {
+    let mut system = spawned_system(Config::default().threads(1));
+    let log = new_log();
+    let recorder = bounded(&mut system, 1, Overflow::Block, &log);
+
+    let sender = {
+        let recorder = recorder.clone();
+        thread::spawn(move || {
+            for note in 0..20 {
+                recorder.send(Note(note));
+            }
+        })
+    };
+    sender.join().unwrap();
+
+    assert!(common::eventually(|| events(&log).len() == 20));
+    assert_eq!(events(&log), notes(&(0..20).collect::<Vec<_>>()));
+    assert_eq!(recorder.overflow_count(), 0);
+    assert_eq!(system.dead_letters().count(), 0);
+    assert!(system.graceful_shutdown().is_ok());
+}
@@ -0,0 +1,216 @@
+//! Actors tested deterministically, by stepping the system on the test's own thread (see
+//! `System::start_manual`).
+extern crate romeo;
+
+mod common;
+
+use common::{events, manual_system, new_log, Log};
+use romeo::actor::*;
+use romeo::address::AskError;
+use romeo::system::Config;
+use romeo::{Address, System};
+
+use std::time::{Duration, Instant};
+
+struct Counter {
+    name: &'static str,
+    count: u32,
+    log: Log,
+}
+impl Counter {
+    fn record(&self, event: String) {
+        common::record(&self.log, format!("{} {}", self.name, event));
+    }
+}
+impl Actor for Counter {
+    fn pre_stop(&mut self) {
+        self.record("pre_stop".to_string());
+    }
+
+    fn post_restart(&mut self, reason: &RestartReason) {
+        self.record(format!("post_restart {:?}", reason));
+    }
+}
+
+struct CounterProps {
+    name: &'static str,
+    log: Log,
+}
+impl Props for CounterProps {}
+impl ActorConstructable<CounterProps> for Counter {
+    fn new(props: &CounterProps) -> Self {
+        Counter {
+            name: props.name,
+            count: 0,
+            log: props.log.clone(),
+        }
+    }
+}
+
+struct Add(u32);
+impl Receives<Add> for Counter {
+    type Response = u32;
+    fn receive(&mut self, msg: Add, _: &Context<Self>) -> Result<u32, Failure> {
+        self.count += msg.0;
+        Ok(self.count)
+    }
+}
+
+struct Fail;
+impl Receives<Fail> for Counter {
+    type Response = ();
+    fn receive(&mut self, _: Fail, _: &Context<Self>) -> Result<(), Failure> {
+        Err("broken".into())
+    }
+}
+
+struct Stop;
+impl Receives<Stop> for Counter {
+    type Response = ();
+    fn receive(&mut self, _: Stop, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.stop();
+        Ok(())
+    }
+}
+
+struct Spawn(&'static str);
+impl Receives<Spawn> for Counter {
+    type Response = Address<Counter>;
+    fn receive(&mut self, msg: Spawn, ctx: &Context<Self>) -> Result<Address<Counter>, Failure> {
+        Ok(ctx.spawn(CounterProps {
+            name: msg.0,
+            log: self.log.clone(),
+        }))
+    }
+}
+
+struct Watch(Address<Counter>);
+impl Receives<Watch> for Counter {
+    type Response = ();
+    fn receive(&mut self, msg: Watch, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.watch(&msg.0);
+        Ok(())
+    }
+}
+
+impl Receives<Terminated> for Counter {
+    type Response = ();
+    fn receive(&mut self, msg: Terminated, _: &Context<Self>) -> Result<(), Failure> {
+        self.record(format!("saw {} terminate", msg.id));
+        Ok(())
+    }
+}
+
+/// Keeps sending itself (through the given address) another message, so it is never idle
+struct Bounce(Address<Counter>);
+impl Receives<Bounce> for Counter {
+    type Response = ();
+    fn receive(&mut self, msg: Bounce, _: &Context<Self>) -> Result<(), Failure> {
+        msg.0.send(Bounce(msg.0.clone()));
+        Ok(())
+    }
+}
+
+fn counter(system: &mut System, name: &'static str, log: &Log) -> Address<Counter> {
+    system.new_actor(CounterProps { name, log: log.clone() })
+}
+
+#[test]
+fn ask_is_replied_to_once_the_system_is_stepped() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let counter = counter(&mut system, "counter", &log);
+
+    counter.send(Add(1));
+    let reply = counter.ask(Add(2));
+    assert_eq!(reply.poll(), Ok(None));
+
+    system.run_until_idle();
+    assert_eq!(reply.poll(), Ok(Some(3)));
+
+    let failed = counter.ask(Fail);
+    system.run_until_idle();
+    assert_eq!(failed.poll(), Err(AskError::Failed("broken".to_string())));
+}
+
+#[test]
+fn stopping_a_parent_stops_its_children_first() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let parent = counter(&mut system, "parent", &log);
+
+    let first = parent.ask(Spawn("first"));
+    let second = parent.ask(Spawn("second"));
+    system.run_until_idle();
+    let first = first.poll().unwrap().unwrap();
+    let second = second.poll().unwrap().unwrap();
+
+    parent.send(Stop);
+    system.run_until_idle();
+
+    let stopped = events(&log);
+    assert_eq!(stopped.len(), 3);
+    assert!(stopped[..2].contains(&"first pre_stop".to_string()));
+    assert!(stopped[..2].contains(&"second pre_stop".to_string()));
+    assert_eq!(stopped[2], "parent pre_stop");
+
+    let first = first.ask(Add(1));
+    let second = second.ask(Add(1));
+    system.run_until_idle();
+    assert_eq!(first.poll(), Err(AskError::Stopped));
+    assert_eq!(second.poll(), Err(AskError::Stopped));
+}
+
+#[test]
+fn watchers_are_told_when_an_actor_stops() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let watcher = counter(&mut system, "watcher", &log);
+    let watched = counter(&mut system, "watched", &log);
+
+    watcher.send(Watch(watched.clone()));
+    system.run_until_idle();
+    assert!(events(&log).is_empty());
+
+    watched.send(Stop);
+    system.run_until_idle();
+    assert_eq!(
+        events(&log),
+        vec![
+            "watched pre_stop".to_string(),
+            format!("watcher saw {} terminate", watched.id()),
+        ]
+    );
+}
+
+#[test]
+fn a_failing_actor_is_restarted_with_fresh_state() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let counter = counter(&mut system, "counter", &log);
+
+    let before = counter.ask(Add(5));
+    counter.send(Fail);
+    let after = counter.ask(Add(1));
+    system.run_until_idle();
+
+    assert_eq!(before.poll(), Ok(Some(5)));
+    assert_eq!(after.poll(), Ok(Some(1)));
+    let restarted = events(&log);
+    assert_eq!(restarted.len(), 2);
+    assert_eq!(restarted[0], "counter pre_stop");
+    assert!(restarted[1].starts_with("counter post_restart Failed"));
+}
+
+#[test]
+fn shutdown_of_a_busy_system_is_bounded_by_the_timeout() {
+    let mut system = manual_system(Config::default().shutdown_timeout(Duration::from_millis(50)));
+    let log = new_log();
+    let busy = counter(&mut system, "busy", &log);
+    busy.send(Bounce(busy.clone()));
+
+    let started = Instant::now();
+    assert!(system.graceful_shutdown().is_ok());
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(events(&log), vec!["busy pre_stop".to_string()]);
+}
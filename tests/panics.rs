@@ -0,0 +1,149 @@
+//! Panics of actor code, which fail only the actor that panicked.
+extern crate romeo;
+
+mod common;
+
+use common::{events, manual_system, new_log, Log};
+use romeo::actor::*;
+use romeo::address::AskError;
+use romeo::system::{Config, System};
+use romeo::Address;
+
+struct Fragile {
+    count: u32,
+    panic_on_failure: bool,
+    panic_on_stop: bool,
+    log: Log,
+}
+impl Actor for Fragile {
+    fn pre_stop(&mut self) {
+        common::record(&self.log, "pre_stop".to_string());
+        if self.panic_on_stop {
+            panic!("pre_stop broke");
+        }
+    }
+
+    fn post_restart(&mut self, _: &RestartReason) {
+        common::record(&self.log, "post_restart".to_string());
+    }
+
+    fn on_failure(&self, _: &Failure) -> Directive {
+        if self.panic_on_failure {
+            panic!("on_failure broke");
+        }
+        Directive::Restart
+    }
+}
+
+/// Where the actor panics, besides its `Panic` handler
+#[derive(Clone, Copy, Default)]
+struct FragileProps {
+    panic_on_failure: bool,
+    panic_on_stop: bool,
+}
+
+struct FragileWithLog(FragileProps, Log);
+impl Props for FragileWithLog {}
+impl ActorConstructable<FragileWithLog> for Fragile {
+    fn new(props: &FragileWithLog) -> Self {
+        Fragile {
+            count: 0,
+            panic_on_failure: props.0.panic_on_failure,
+            panic_on_stop: props.0.panic_on_stop,
+            log: props.1.clone(),
+        }
+    }
+}
+
+struct Add(u32);
+impl Receives<Add> for Fragile {
+    type Response = u32;
+    fn receive(&mut self, msg: Add, _: &Context<Self>) -> Result<u32, Failure> {
+        self.count += msg.0;
+        Ok(self.count)
+    }
+}
+
+struct Panic;
+impl Receives<Panic> for Fragile {
+    type Response = ();
+    fn receive(&mut self, _: Panic, _: &Context<Self>) -> Result<(), Failure> {
+        panic!("boom");
+    }
+}
+
+struct Stop;
+impl Receives<Stop> for Fragile {
+    type Response = ();
+    fn receive(&mut self, _: Stop, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.stop();
+        Ok(())
+    }
+}
+
+fn fragile(system: &mut System, props: FragileProps, log: &Log) -> Address<Fragile> {
+    system.new_actor(FragileWithLog(props, log.clone()))
+}
+
+fn add(system: &mut System, actor: &Address<Fragile>, n: u32) -> Result<Option<u32>, AskError> {
+    let reply = actor.ask(Add(n));
+    system.run_until_idle();
+    reply.poll()
+}
+
+#[test]
+fn a_panicking_handler_fails_its_actor_only() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let panicky = fragile(&mut system, FragileProps::default(), &log);
+    let neighbour = fragile(&mut system, FragileProps::default(), &new_log());
+
+    assert_eq!(add(&mut system, &panicky, 5), Ok(Some(5)));
+    assert_eq!(add(&mut system, &neighbour, 5), Ok(Some(5)));
+
+    let panicked = panicky.ask(Panic);
+    system.run_until_idle();
+    assert_eq!(panicked.poll(), Err(AskError::Failed("handler panicked: boom".to_string())));
+    assert_eq!(events(&log), vec!["pre_stop", "post_restart"]);
+
+    // the panicking actor starts over, while its neighbour on the same thread carries on
+    assert_eq!(add(&mut system, &panicky, 1), Ok(Some(1)));
+    assert_eq!(add(&mut system, &neighbour, 1), Ok(Some(6)));
+}
+
+#[test]
+fn an_actor_that_panics_deciding_on_its_failure_escalates() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let props = FragileProps {
+        panic_on_failure: true,
+        ..FragileProps::default()
+    };
+    let panicky = fragile(&mut system, props, &log);
+
+    panicky.send(Panic);
+    system.run_until_idle();
+
+    // without a supervisor to escalate to, the actor is stopped
+    assert_eq!(events(&log), vec!["pre_stop"]);
+    assert_eq!(add(&mut system, &panicky, 1), Err(AskError::Stopped));
+}
+
+#[test]
+fn an_actor_that_panics_while_stopping_still_stops() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let props = FragileProps {
+        panic_on_stop: true,
+        ..FragileProps::default()
+    };
+    let panicky = fragile(&mut system, props, &log);
+    let neighbour = fragile(&mut system, FragileProps::default(), &new_log());
+
+    panicky.send(Stop);
+    system.run_until_idle();
+
+    assert_eq!(events(&log), vec!["pre_stop"]);
+    assert_eq!(add(&mut system, &panicky, 1), Err(AskError::Stopped));
+    assert_eq!(add(&mut system, &neighbour, 1), Ok(Some(1)));
+}
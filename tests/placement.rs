@@ -0,0 +1,159 @@
+//! Placement strategies choosing the scheduler new actors start out on.
+extern crate romeo;
+
+mod common;
+
+use common::spawned_system;
+use romeo::actor::*;
+use romeo::placement::*;
+use romeo::system::Config;
+use romeo::Address;
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// A placement decision: the scheduler chosen, along with the parent and key of the request
+type Decision = (usize, Option<usize>, Option<u64>);
+
+/// Records the decisions of the strategy it wraps
+struct Recorded<P> {
+    inner: P,
+    decisions: Arc<Mutex<Vec<Decision>>>,
+}
+impl<P: Placement> Placement for Recorded<P> {
+    fn place(&self, request: &PlacementRequest) -> usize {
+        let chosen = self.inner.place(request);
+        self.decisions.lock().unwrap().push((chosen, request.parent, request.key));
+        chosen
+    }
+}
+
+fn recorded<P: Placement>(inner: P) -> (Recorded<P>, Arc<Mutex<Vec<Decision>>>) {
+    let decisions = Arc::new(Mutex::new(vec![]));
+    (Recorded { inner, decisions: decisions.clone() }, decisions)
+}
+
+fn chosen(decisions: &Arc<Mutex<Vec<Decision>>>) -> Vec<usize> {
+    decisions.lock().unwrap().iter().map(|d| d.0).collect()
+}
+
+struct Node;
+impl Actor for Node {}
+
+struct NodeProps;
+impl Props for NodeProps {}
+impl ActorConstructable<NodeProps> for Node {
+    fn new(_: &NodeProps) -> Self {
+        Node
+    }
+}
+
+/// Spawn a child of the node
+struct SpawnChild;
+impl Receives<SpawnChild> for Node {
+    type Response = Address<Node>;
+    fn receive(&mut self, _: SpawnChild, ctx: &Context<Self>) -> Result<Address<Node>, Failure> {
+        Ok(ctx.spawn(NodeProps))
+    }
+}
+
+/// The name of the thread the node runs on
+struct Where;
+impl Receives<Where> for Node {
+    type Response = String;
+    fn receive(&mut self, _: Where, _: &Context<Self>) -> Result<String, Failure> {
+        Ok(thread::current().name().unwrap_or_default().to_string())
+    }
+}
+
+fn wait<R>(reply: romeo::address::Reply<R>) -> R {
+    reply.wait_timeout(Duration::from_secs(5)).unwrap()
+}
+
+#[test]
+fn round_robin_places_actors_on_each_scheduler_in_turn() {
+    let (placement, decisions) = recorded(RoundRobin::new());
+    let mut system = spawned_system(Config::default().threads(3).placement(placement));
+    for _ in 0..6 {
+        let _: Address<Node> = system.new_actor(NodeProps);
+    }
+    assert_eq!(chosen(&decisions), vec![0, 1, 2, 0, 1, 2]);
+    assert!(system.graceful_shutdown().is_ok());
+}
+
+#[test]
+fn hash_of_key_places_actors_with_the_same_key_together() {
+    let (placement, decisions) = recorded(HashOfKey);
+    let mut system = spawned_system(Config::default().threads(4).placement(placement));
+    for key in &["left", "right", "left", "right", "left"] {
+        let _: Address<Node> = system.new_actor_with(NodeProps, ActorConfig::default().placement_key(key));
+    }
+
+    let decisions = decisions.lock().unwrap().clone();
+    assert_eq!(decisions.len(), 5);
+    for (i, a) in decisions.iter().enumerate() {
+        assert!(a.2.is_some());
+        for b in decisions[i + 1..].iter().filter(|b| b.2 == a.2) {
+            assert_eq!(a.0, b.0);
+        }
+    }
+    assert!(system.graceful_shutdown().is_ok());
+}
+
+#[test]
+fn least_loaded_spreads_actors_over_the_schedulers() {
+    let (placement, decisions) = recorded(LeastLoaded::by_actor_count());
+    let mut system = spawned_system(Config::default().threads(3).placement(placement));
+    for _ in 0..3 {
+        let _: Address<Node> = system.new_actor(NodeProps);
+    }
+    let mut chosen = chosen(&decisions);
+    chosen.sort();
+    assert_eq!(chosen, vec![0, 1, 2]);
+    assert!(system.graceful_shutdown().is_ok());
+}
+
+#[test]
+fn co_locate_with_parent_places_children_with_their_parent() {
+    let (placement, decisions) = recorded(CoLocateWithParent);
+    let mut system = spawned_system(Config::default().threads(3).placement(placement));
+    let parent: Address<Node> = system.new_actor(NodeProps);
+    for _ in 0..3 {
+        wait(parent.ask(SpawnChild));
+    }
+
+    let decisions = decisions.lock().unwrap().clone();
+    assert_eq!(decisions.len(), 4);
+    assert_eq!(decisions[0].1, None);
+    for child in &decisions[1..] {
+        assert_eq!(child.1, Some(child.0));
+    }
+    assert!(system.graceful_shutdown().is_ok());
+}
+
+#[test]
+fn actors_may_override_the_system_wide_placement() {
+    let (system_wide, system_decisions) = recorded(RoundRobin::new());
+    let (own, own_decisions) = recorded(RoundRobin::new());
+    let mut system = spawned_system(Config::default().threads(2).placement(system_wide));
+
+    let _: Address<Node> = system.new_actor(NodeProps);
+    let _: Address<Node> = system.new_actor_with(NodeProps, ActorConfig::default().placement(own));
+    assert_eq!(chosen(&system_decisions), vec![0]);
+    assert_eq!(chosen(&own_decisions), vec![0]);
+    assert!(system.graceful_shutdown().is_ok());
+}
+
+#[test]
+fn colocated_actors_share_a_thread() {
+    let mut system = spawned_system(Config::default().threads(4).placement(RoundRobin::new()));
+    let first: Address<Node> = system.new_actor(NodeProps);
+    let home = wait(first.ask(Where));
+
+    for _ in 0..3 {
+        let colocated: Address<Node> = system.new_actor_with(NodeProps, ActorConfig::default().colocate_with(&first));
+        assert_eq!(wait(colocated.ask(Where)), home);
+    }
+    assert!(system.graceful_shutdown().is_ok());
+}
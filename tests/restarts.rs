@@ -0,0 +1,164 @@
+//! Restart backoff (see `romeo::supervision::Backoff`) and what becomes of the waiting messages
+//! of a restarted actor (see `romeo::mailbox::MailboxRetention`).
+extern crate romeo;
+
+mod common;
+
+use common::{events, manual_system, new_log, Log};
+use romeo::actor::*;
+use romeo::mailbox::MailboxRetention;
+use romeo::supervision::Backoff;
+use romeo::system::{Config, System};
+use romeo::Address;
+
+use std::thread;
+use std::time::Duration;
+
+const MIN_BACKOFF: Duration = Duration::from_millis(50);
+
+struct Counter {
+    count: u32,
+    log: Log,
+}
+impl Actor for Counter {
+    fn post_restart(&mut self, _: &RestartReason) {
+        common::record(&self.log, "post_restart".to_string());
+    }
+}
+
+struct CounterProps(Log);
+impl Props for CounterProps {}
+impl ActorConstructable<CounterProps> for Counter {
+    fn new(props: &CounterProps) -> Self {
+        Counter {
+            count: 0,
+            log: props.0.clone(),
+        }
+    }
+}
+
+struct Add(u32);
+impl Receives<Add> for Counter {
+    type Response = u32;
+    fn receive(&mut self, msg: Add, _: &Context<Self>) -> Result<u32, Failure> {
+        self.count += msg.0;
+        Ok(self.count)
+    }
+}
+
+struct Fail;
+impl Receives<Fail> for Counter {
+    type Response = ();
+    fn receive(&mut self, _: Fail, _: &Context<Self>) -> Result<(), Failure> {
+        Err("broken".into())
+    }
+}
+
+struct Restart;
+impl Receives<Restart> for Counter {
+    type Response = ();
+    fn receive(&mut self, _: Restart, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.restart();
+        Ok(())
+    }
+}
+
+fn counter(system: &mut System, config: ActorConfig, log: &Log) -> Address<Counter> {
+    system.new_actor_with(CounterProps(log.clone()), config)
+}
+
+fn backing_off() -> ActorConfig {
+    ActorConfig::default().restart_backoff(Backoff::exponential(MIN_BACKOFF, Duration::from_secs(1)).jitter(0.0))
+}
+
+fn wait(system: &mut System, duration: Duration) {
+    thread::sleep(duration);
+    system.run_until_idle();
+}
+
+#[test]
+fn restarts_after_a_failure_are_held_back() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let counter = counter(&mut system, backing_off(), &log);
+
+    counter.send(Add(5));
+    counter.send(Fail);
+    let after = counter.ask(Add(1));
+    system.run_until_idle();
+    assert_eq!(after.poll(), Ok(None));
+    assert!(events(&log).is_empty());
+
+    wait(&mut system, MIN_BACKOFF * 2);
+    assert_eq!(events(&log), vec!["post_restart"]);
+    assert_eq!(after.poll(), Ok(Some(1)));
+}
+
+#[test]
+fn repeated_failures_back_off_for_longer() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let counter = counter(&mut system, backing_off(), &log);
+
+    counter.send(Fail);
+    system.run_until_idle();
+    wait(&mut system, MIN_BACKOFF * 2);
+    assert_eq!(events(&log).len(), 1);
+
+    // the second restart is held back for twice as long
+    counter.send(Fail);
+    system.run_until_idle();
+    wait(&mut system, MIN_BACKOFF + MIN_BACKOFF / 5);
+    assert_eq!(events(&log).len(), 1);
+    wait(&mut system, MIN_BACKOFF * 2);
+    assert_eq!(events(&log).len(), 2);
+}
+
+#[test]
+fn requested_restarts_are_never_held_back() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let counter = counter(&mut system, backing_off(), &log);
+
+    counter.send(Add(5));
+    counter.send(Restart);
+    let after = counter.ask(Add(1));
+    system.run_until_idle();
+    assert_eq!(events(&log), vec!["post_restart"]);
+    assert_eq!(after.poll(), Ok(Some(1)));
+}
+
+#[test]
+fn waiting_messages_are_kept_across_a_restart_by_default() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let counter = counter(&mut system, ActorConfig::default(), &log);
+
+    counter.send(Add(5));
+    counter.send(Fail);
+    let first = counter.ask(Add(1));
+    let second = counter.ask(Add(2));
+    system.run_until_idle();
+    assert_eq!(first.poll(), Ok(Some(1)));
+    assert_eq!(second.poll(), Ok(Some(3)));
+    assert_eq!(system.dead_letters().count(), 0);
+}
+
+#[test]
+fn waiting_messages_can_be_discarded_on_restart() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let config = ActorConfig::default().mailbox_on_restart(MailboxRetention::Discard);
+    let counter = counter(&mut system, config, &log);
+
+    counter.send(Fail);
+    counter.send(Add(1));
+    counter.send(Add(2));
+    system.run_until_idle();
+    assert_eq!(events(&log), vec!["post_restart"]);
+    assert_eq!(system.dead_letters().count(), 2);
+
+    let after = counter.ask(Add(3));
+    system.run_until_idle();
+    assert_eq!(after.poll(), Ok(Some(3)));
+}
@@ -0,0 +1,117 @@
+//! Running the system on the caller's thread, see `System::run`.
+extern crate romeo;
+
+mod common;
+
+use common::{events, new_log, Log};
+use romeo::actor::*;
+use romeo::system::Config;
+use romeo::{Address, System};
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+use std::time::Duration;
+
+struct Worker {
+    log: Log,
+    threads: Arc<Mutex<Vec<ThreadId>>>,
+}
+impl Actor for Worker {}
+
+struct WorkerProps(Log, Arc<Mutex<Vec<ThreadId>>>);
+impl Props for WorkerProps {}
+impl ActorConstructable<WorkerProps> for Worker {
+    fn new(props: &WorkerProps) -> Self {
+        Worker {
+            log: props.0.clone(),
+            threads: props.1.clone(),
+        }
+    }
+}
+
+struct Work(u32);
+impl Receives<Work> for Worker {
+    type Response = ();
+    fn receive(&mut self, msg: Work, _: &Context<Self>) -> Result<(), Failure> {
+        self.threads.lock().unwrap().push(thread::current().id());
+        common::record(&self.log, format!("work {}", msg.0));
+        Ok(())
+    }
+}
+
+struct Shutdown;
+impl Receives<Shutdown> for Worker {
+    type Response = ();
+    fn receive(&mut self, _: Shutdown, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.shutdown_system();
+        Ok(())
+    }
+}
+
+fn system() -> System {
+    let mut system = System::new();
+    system.with_config(Config::default().threads(4));
+    system
+}
+
+#[test]
+fn run_handles_messages_on_the_caller_thread_until_an_actor_shuts_the_system_down() {
+    let log = new_log();
+    let threads = Arc::new(Mutex::new(vec![]));
+    let props = WorkerProps(log.clone(), threads.clone());
+
+    let ran = system().run(move |system| {
+        let worker: Address<Worker> = system.new_actor(props);
+        worker.send(Work(1));
+        worker.send(Work(2));
+        worker.send(Shutdown);
+    });
+
+    assert!(ran.is_ok());
+    assert_eq!(events(&log), vec!["work 1", "work 2"]);
+    assert!(threads.lock().unwrap().iter().all(|id| *id == thread::current().id()));
+}
+
+#[test]
+fn run_returns_once_shut_down_from_another_thread() {
+    let log = new_log();
+    let props = WorkerProps(log.clone(), Arc::new(Mutex::new(vec![])));
+
+    let ran = system().run(move |system| {
+        let worker: Address<Worker> = system.new_actor(props);
+        worker.send(Work(1));
+        let handle = system.shutdown_handle();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            handle.shutdown();
+        });
+    });
+
+    assert!(ran.is_ok());
+    assert_eq!(events(&log), vec!["work 1"]);
+}
+
+#[test]
+fn run_with_uses_the_caller_thread_as_one_of_the_schedulers() {
+    let log = new_log();
+    let threads = Arc::new(Mutex::new(vec![]));
+    let props: Vec<WorkerProps> = (0..8).map(|_| WorkerProps(log.clone(), threads.clone())).collect();
+
+    let ran = system().run_with(2, move |system| {
+        let workers: Vec<Address<Worker>> = props.into_iter().map(|props| system.new_actor(props)).collect();
+        for (n, worker) in workers.iter().enumerate() {
+            worker.send(Work(n as u32));
+        }
+        let last = workers[0].clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            last.send(Shutdown);
+        });
+    });
+
+    assert!(ran.is_ok());
+    assert_eq!(events(&log).len(), 8);
+    let threads: HashSet<ThreadId> = threads.lock().unwrap().iter().cloned().collect();
+    assert!(threads.len() <= 2);
+}
@@ -0,0 +1,117 @@
+//! Actors setting messages aside to handle later, see `Context::stash`.
+extern crate romeo;
+
+mod common;
+
+use common::{events, manual_system, new_log, Log};
+use romeo::actor::*;
+use romeo::address::AskError;
+use romeo::system::Config;
+use romeo::{Address, System};
+
+/// Stashes requests until it is ready for them
+struct Loader {
+    ready: bool,
+    log: Log,
+}
+impl Actor for Loader {}
+
+struct LoaderProps(Log);
+impl Props for LoaderProps {}
+impl ActorConstructable<LoaderProps> for Loader {
+    fn new(props: &LoaderProps) -> Self {
+        Loader {
+            ready: false,
+            log: props.0.clone(),
+        }
+    }
+}
+
+struct Request(u32);
+impl Receives<Request> for Loader {
+    type Response = u32;
+    fn receive(&mut self, msg: Request, ctx: &Context<Self>) -> Result<u32, Failure> {
+        if !self.ready {
+            ctx.stash(msg)?;
+            return Ok(0);
+        }
+        common::record(&self.log, format!("request {}", msg.0));
+        Ok(msg.0)
+    }
+}
+
+struct Ready;
+impl Receives<Ready> for Loader {
+    type Response = ();
+    fn receive(&mut self, _: Ready, ctx: &Context<Self>) -> Result<(), Failure> {
+        self.ready = true;
+        ctx.unstash_all();
+        Ok(())
+    }
+}
+
+struct Stop;
+impl Receives<Stop> for Loader {
+    type Response = ();
+    fn receive(&mut self, _: Stop, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.stop();
+        Ok(())
+    }
+}
+
+fn loader(system: &mut System, config: ActorConfig, log: &Log) -> Address<Loader> {
+    system.new_actor_with(LoaderProps(log.clone()), config)
+}
+
+#[test]
+fn stashed_messages_are_handled_in_order_once_unstashed() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let loader = loader(&mut system, ActorConfig::default(), &log);
+
+    let first = loader.ask(Request(1));
+    loader.send(Request(2));
+    system.run_until_idle();
+    assert_eq!(first.poll(), Ok(None));
+    assert!(events(&log).is_empty());
+
+    loader.send(Ready);
+    loader.send(Request(3));
+    system.run_until_idle();
+    assert_eq!(events(&log), vec!["request 1", "request 2", "request 3"]);
+    assert_eq!(first.poll(), Ok(Some(1)));
+}
+
+#[test]
+fn a_full_stash_fails_the_actor() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let loader = loader(&mut system, ActorConfig::default().stash_capacity(1), &log);
+
+    let stashed = loader.ask(Request(1));
+    let refused = loader.ask(Request(2));
+    system.run_until_idle();
+    assert_eq!(refused.poll(), Err(AskError::Failed("actor's stash is full".to_string())));
+
+    // the stash outlives the restart
+    loader.send(Ready);
+    system.run_until_idle();
+    assert_eq!(stashed.poll(), Ok(Some(1)));
+    assert_eq!(events(&log), vec!["request 1"]);
+}
+
+#[test]
+fn stashed_messages_become_dead_letters_once_the_actor_stops() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let loader = loader(&mut system, ActorConfig::default(), &log);
+
+    let stashed = loader.ask(Request(1));
+    loader.send(Request(2));
+    loader.send(Stop);
+    system.run_until_idle();
+
+    assert_eq!(stashed.poll(), Err(AskError::Stopped));
+    assert_eq!(system.dead_letters().count(), 2);
+    assert!(events(&log).is_empty());
+}
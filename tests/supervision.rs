@@ -0,0 +1,271 @@
+//! Supervision of child actors: the directives chosen by a failing child, the strategies of
+//! its parent, and restart limits.
+extern crate romeo;
+
+mod common;
+
+use common::{events, manual_system, new_log, record, Log};
+use romeo::actor::*;
+use romeo::address::AskError;
+use romeo::supervision::SupervisorStrategy;
+use romeo::system::Config;
+use romeo::{Address, System};
+
+use std::time::Duration;
+
+fn describe(reason: &RestartReason) -> String {
+    match reason {
+        RestartReason::Failed(failure) => format!("failed: {}", failure),
+        RestartReason::SiblingFailed(_, failure) => format!("sibling failed: {}", failure),
+        RestartReason::Requested => "requested".to_string(),
+    }
+}
+
+// ---
+// Parent
+// ---
+
+struct Parent {
+    strategy: SupervisorStrategy,
+    log: Log,
+}
+impl Actor for Parent {
+    fn supervisor_strategy(&self) -> SupervisorStrategy {
+        self.strategy
+    }
+
+    fn post_restart(&mut self, reason: &RestartReason) {
+        record(&self.log, format!("parent restarted, {}", describe(reason)));
+    }
+}
+
+struct ParentProps {
+    strategy: SupervisorStrategy,
+    log: Log,
+}
+impl Props for ParentProps {}
+impl ActorConstructable<ParentProps> for Parent {
+    fn new(props: &ParentProps) -> Self {
+        Parent {
+            strategy: props.strategy,
+            log: props.log.clone(),
+        }
+    }
+}
+
+/// Create a child worker with the given name, failing with the given directive
+struct SpawnWorker(&'static str, Directive);
+impl Receives<SpawnWorker> for Parent {
+    type Response = Address<Worker>;
+    fn receive(&mut self, msg: SpawnWorker, ctx: &Context<Self>) -> Result<Address<Worker>, Failure> {
+        Ok(ctx.spawn(WorkerProps {
+            name: msg.0,
+            directive: msg.1,
+            log: self.log.clone(),
+        }))
+    }
+}
+
+// ---
+// Worker
+// ---
+
+struct Worker {
+    name: &'static str,
+    directive: Directive,
+    handled: u32,
+    log: Log,
+}
+impl Actor for Worker {
+    fn pre_stop(&mut self) {
+        record(&self.log, format!("{} stopped", self.name));
+    }
+
+    fn pre_restart(&mut self, _: &RestartReason, _: Option<&'static str>) {}
+
+    fn post_restart(&mut self, reason: &RestartReason) {
+        record(&self.log, format!("{} restarted, {}", self.name, describe(reason)));
+    }
+
+    fn on_failure(&self, _: &Failure) -> Directive {
+        self.directive
+    }
+}
+
+struct WorkerProps {
+    name: &'static str,
+    directive: Directive,
+    log: Log,
+}
+impl Props for WorkerProps {}
+impl ActorConstructable<WorkerProps> for Worker {
+    fn new(props: &WorkerProps) -> Self {
+        Worker {
+            name: props.name,
+            directive: props.directive,
+            handled: 0,
+            log: props.log.clone(),
+        }
+    }
+}
+
+struct Fail;
+impl Receives<Fail> for Worker {
+    type Response = ();
+    fn receive(&mut self, _: Fail, _: &Context<Self>) -> Result<(), Failure> {
+        Err("broken".into())
+    }
+}
+
+/// Count the messages handled by the worker, since it was (re)started
+struct Count;
+impl Receives<Count> for Worker {
+    type Response = u32;
+    fn receive(&mut self, _: Count, _: &Context<Self>) -> Result<u32, Failure> {
+        self.handled += 1;
+        Ok(self.handled)
+    }
+}
+
+// ---
+// Tests
+// ---
+
+fn parent(system: &mut System, strategy: SupervisorStrategy, log: &Log) -> Address<Parent> {
+    system.new_actor(ParentProps {
+        strategy,
+        log: log.clone(),
+    })
+}
+
+fn workers(system: &mut System, parent: &Address<Parent>, names: &[&'static str], directive: Directive) -> Vec<Address<Worker>> {
+    let replies: Vec<_> = names.iter().map(|name| parent.ask(SpawnWorker(name, directive))).collect();
+    system.run_until_idle();
+    replies.into_iter().map(|reply| reply.poll().unwrap().unwrap()).collect()
+}
+
+fn sorted(mut events: Vec<String>) -> Vec<String> {
+    events.sort();
+    events
+}
+
+#[test]
+fn one_for_one_restarts_only_the_failed_child() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let parent = parent(&mut system, SupervisorStrategy::one_for_one(10, Duration::from_secs(60)), &log);
+    let workers = workers(&mut system, &parent, &["a", "b"], Directive::Restart);
+
+    workers[0].send(Count);
+    workers[0].send(Fail);
+    let count = workers[0].ask(Count);
+    system.run_until_idle();
+
+    assert_eq!(events(&log), vec!["a restarted, failed: broken".to_string()]);
+    assert_eq!(count.poll(), Ok(Some(1)));
+}
+
+#[test]
+fn all_for_one_restarts_every_child() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let parent = parent(&mut system, SupervisorStrategy::all_for_one(10, Duration::from_secs(60)), &log);
+    let workers = workers(&mut system, &parent, &["a", "b"], Directive::Restart);
+
+    workers[1].send(Fail);
+    system.run_until_idle();
+
+    assert_eq!(
+        sorted(events(&log)),
+        vec!["a restarted, sibling failed: broken".to_string(), "b restarted, failed: broken".to_string()]
+    );
+}
+
+#[test]
+fn rest_for_one_restarts_the_children_created_after_the_failed_one() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let parent = parent(&mut system, SupervisorStrategy::rest_for_one(10, Duration::from_secs(60)), &log);
+    let workers = workers(&mut system, &parent, &["a", "b", "c"], Directive::Restart);
+
+    workers[1].send(Fail);
+    system.run_until_idle();
+
+    assert_eq!(
+        sorted(events(&log)),
+        vec!["b restarted, failed: broken".to_string(), "c restarted, sibling failed: broken".to_string()]
+    );
+}
+
+#[test]
+fn exceeding_the_restart_limit_stops_the_child_and_fails_the_parent() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let parent = parent(&mut system, SupervisorStrategy::one_for_one(1, Duration::from_secs(60)), &log);
+    let workers = workers(&mut system, &parent, &["a"], Directive::Restart);
+
+    workers[0].send(Fail);
+    workers[0].send(Fail);
+    system.run_until_idle();
+
+    let events = events(&log);
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0], "a restarted, failed: broken");
+    assert_eq!(events[1], "a stopped");
+    assert!(events[2].starts_with("parent restarted, failed: child actor"));
+    assert!(events[2].ends_with("exceeded 1 restarts within 60s"));
+
+    let reply = workers[0].ask(Count);
+    system.run_until_idle();
+    assert_eq!(reply.poll(), Err(AskError::Stopped));
+}
+
+#[test]
+fn an_escalated_failure_fails_the_parent() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let parent = parent(&mut system, SupervisorStrategy::default(), &log);
+    let workers = workers(&mut system, &parent, &["a"], Directive::Escalate);
+
+    workers[0].send(Count);
+    workers[0].send(Fail);
+    let count = workers[0].ask(Count);
+    system.run_until_idle();
+
+    // the child is left as it is, while its parent deals with the failure
+    assert_eq!(events(&log), vec!["parent restarted, failed: broken".to_string()]);
+    assert_eq!(count.poll(), Ok(Some(2)));
+}
+
+#[test]
+fn a_stop_directive_stops_the_failed_child() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let parent = parent(&mut system, SupervisorStrategy::default(), &log);
+    let workers = workers(&mut system, &parent, &["a", "b"], Directive::Stop);
+
+    workers[0].send(Fail);
+    let stopped = workers[0].ask(Count);
+    let sibling = workers[1].ask(Count);
+    system.run_until_idle();
+
+    assert_eq!(events(&log), vec!["a stopped".to_string()]);
+    assert_eq!(stopped.poll(), Err(AskError::Stopped));
+    assert_eq!(sibling.poll(), Ok(Some(1)));
+}
+
+#[test]
+fn a_resumed_child_keeps_its_state() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let parent = parent(&mut system, SupervisorStrategy::default(), &log);
+    let workers = workers(&mut system, &parent, &["a"], Directive::Resume);
+
+    workers[0].send(Count);
+    workers[0].send(Fail);
+    let count = workers[0].ask(Count);
+    system.run_until_idle();
+
+    assert!(events(&log).is_empty());
+    assert_eq!(count.poll(), Ok(Some(2)));
+}
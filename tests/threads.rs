@@ -0,0 +1,99 @@
+//! The configuration of scheduler threads: names, stack size, core pinning and hooks.
+extern crate romeo;
+
+mod common;
+
+use common::spawned_system;
+use romeo::actor::*;
+use romeo::system::Config;
+use romeo::Address;
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+struct Worker;
+impl Actor for Worker {}
+
+struct WorkerProps;
+impl Props for WorkerProps {}
+impl ActorConstructable<WorkerProps> for Worker {
+    fn new(_: &WorkerProps) -> Self {
+        Worker
+    }
+}
+
+/// The name of the thread the worker runs on
+struct Where;
+impl Receives<Where> for Worker {
+    type Response = String;
+    fn receive(&mut self, _: Where, _: &Context<Self>) -> Result<String, Failure> {
+        Ok(thread::current().name().unwrap_or_default().to_string())
+    }
+}
+
+fn where_is(worker: &Address<Worker>) -> String {
+    worker.ask(Where).wait_timeout(Duration::from_secs(5)).unwrap()
+}
+
+#[test]
+fn scheduler_threads_are_named_with_the_prefix() {
+    let config = Config::default().threads(3).thread_name_prefix("named").stack_size(4 * 1024 * 1024);
+    let mut system = spawned_system(config);
+
+    for _ in 0..3 {
+        let worker: Address<Worker> = system.new_actor(WorkerProps);
+        assert!(["named-0", "named-1", "named-2"].contains(&where_is(&worker).as_str()));
+    }
+    assert!(system.graceful_shutdown().is_ok());
+}
+
+#[test]
+fn hooks_run_on_every_scheduler_thread() {
+    let started = Arc::new(Mutex::new(vec![]));
+    let stopped = Arc::new(Mutex::new(vec![]));
+    let config = {
+        let started = started.clone();
+        let stopped = stopped.clone();
+        Config::default()
+            .threads(2)
+            .dispatcher("io", 1)
+            .thread_name_prefix("hooked")
+            .on_thread_start(move |id| {
+                let name = thread::current().name().unwrap_or_default().to_string();
+                started.lock().unwrap().push((id, name));
+            })
+            .on_thread_stop(move |id| stopped.lock().unwrap().push(id))
+    };
+    let system = spawned_system(config);
+    assert!(common::eventually(|| started.lock().unwrap().len() == 3));
+    assert!(stopped.lock().unwrap().is_empty());
+    assert!(system.graceful_shutdown().is_ok());
+
+    let mut started = started.lock().unwrap().clone();
+    started.sort();
+    let expected: Vec<(usize, String)> = (0..3).map(|id| (id, format!("hooked-{}", id))).collect();
+    assert_eq!(started, expected);
+
+    let mut stopped = stopped.lock().unwrap().clone();
+    stopped.sort();
+    assert_eq!(stopped, vec![0, 1, 2]);
+}
+
+#[test]
+fn pinning_to_a_missing_core_leaves_the_thread_unpinned() {
+    let pinned = Arc::new(Mutex::new(vec![]));
+    let config = {
+        let pinned = pinned.clone();
+        Config::default().threads(2).affinity(move |id| {
+            pinned.lock().unwrap().push(id);
+            Some(usize::MAX)
+        })
+    };
+    let mut system = spawned_system(config);
+
+    let worker: Address<Worker> = system.new_actor(WorkerProps);
+    where_is(&worker);
+    assert!(common::eventually(|| pinned.lock().unwrap().len() == 2));
+    assert!(system.graceful_shutdown().is_ok());
+}
@@ -0,0 +1,111 @@
+//! Schedulers handling a batch of messages per actor visit, see `Config::throughput`.
+extern crate romeo;
+
+mod common;
+
+use common::{events, new_log, spawned_system, Log};
+use romeo::actor::*;
+use romeo::system::{Config, System};
+use romeo::Address;
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+struct Worker {
+    name: &'static str,
+    log: Log,
+}
+impl Actor for Worker {}
+
+struct WorkerProps(&'static str, Log);
+impl Props for WorkerProps {}
+impl ActorConstructable<WorkerProps> for Worker {
+    fn new(props: &WorkerProps) -> Self {
+        Worker {
+            name: props.0,
+            log: props.1.clone(),
+        }
+    }
+}
+
+struct Work(u32);
+impl Receives<Work> for Worker {
+    type Response = ();
+    fn receive(&mut self, msg: Work, _: &Context<Self>) -> Result<(), Failure> {
+        common::record(&self.log, format!("{}{}", self.name, msg.0));
+        Ok(())
+    }
+}
+
+struct Stop;
+impl Receives<Stop> for Worker {
+    type Response = ();
+    fn receive(&mut self, _: Stop, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.stop();
+        Ok(())
+    }
+}
+
+/// Hold up the worker's thread until released, letting the test know once it is held up
+struct Hold(Sender<()>, Receiver<()>);
+impl Receives<Hold> for Worker {
+    type Response = ();
+    fn receive(&mut self, msg: Hold, _: &Context<Self>) -> Result<(), Failure> {
+        msg.0.send(()).unwrap();
+        msg.1.recv().unwrap();
+        Ok(())
+    }
+}
+
+/// Hold up the only scheduler thread of the system, returning the sender that releases it
+fn hold(system: &mut System) -> Sender<()> {
+    let holder: Address<Worker> = system.new_actor(WorkerProps("holder", new_log()));
+    let (held, is_held) = channel();
+    let (release, released) = channel();
+    holder.send(Hold(held, released));
+    is_held.recv().unwrap();
+    release
+}
+
+fn worker(system: &mut System, name: &'static str, log: &Log) -> Address<Worker> {
+    system.new_actor(WorkerProps(name, log.clone()))
+}
+
+#[test]
+fn actors_take_turns_a_batch_of_messages_at_a_time() {
+    let mut system = spawned_system(Config::default().threads(1).throughput(2));
+    let log = new_log();
+    let x = worker(&mut system, "x", &log);
+    let y = worker(&mut system, "y", &log);
+
+    let release = hold(&mut system);
+    for n in 1..5 {
+        x.send(Work(n));
+    }
+    for n in 1..5 {
+        y.send(Work(n));
+    }
+    release.send(()).unwrap();
+
+    assert!(common::eventually(|| events(&log).len() == 8));
+    assert_eq!(events(&log), vec!["x1", "x2", "y1", "y2", "x3", "x4", "y3", "y4"]);
+    assert!(system.graceful_shutdown().is_ok());
+}
+
+#[test]
+fn a_stop_ends_the_batch() {
+    let mut system = spawned_system(Config::default().threads(1).throughput(10));
+    let log = new_log();
+    let stopping = worker(&mut system, "stopping", &log);
+
+    let release = hold(&mut system);
+    stopping.send(Work(1));
+    stopping.send(Stop);
+    stopping.send(Work(2));
+    stopping.send(Work(3));
+    release.send(()).unwrap();
+
+    let dead_letters = system.dead_letters();
+    assert!(common::eventually(|| dead_letters.count() == 2));
+    assert_eq!(events(&log), vec!["stopping1"]);
+    assert!(system.graceful_shutdown().is_ok());
+}
@@ -0,0 +1,166 @@
+//! Messages scheduled with `Context::schedule_once` and `Context::schedule_repeating`.
+extern crate romeo;
+
+mod common;
+
+use common::{events, manual_system, new_log, Log};
+use romeo::actor::*;
+use romeo::address::AskError;
+use romeo::system::{Config, System};
+use romeo::timer::Timer;
+use romeo::Address;
+
+use std::thread;
+use std::time::Duration;
+
+const TICK: Duration = Duration::from_millis(10);
+
+struct Alarm {
+    log: Log,
+    timer: Option<Timer>,
+}
+impl Actor for Alarm {}
+
+struct AlarmProps(Log);
+impl Props for AlarmProps {}
+impl ActorConstructable<AlarmProps> for Alarm {
+    fn new(props: &AlarmProps) -> Self {
+        Alarm {
+            log: props.0.clone(),
+            timer: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Ring(&'static str);
+impl Receives<Ring> for Alarm {
+    type Response = ();
+    fn receive(&mut self, msg: Ring, _: &Context<Self>) -> Result<(), Failure> {
+        common::record(&self.log, msg.0.to_string());
+        Ok(())
+    }
+}
+
+struct Once(Duration);
+impl Receives<Once> for Alarm {
+    type Response = ();
+    fn receive(&mut self, msg: Once, ctx: &Context<Self>) -> Result<(), Failure> {
+        self.timer = Some(ctx.schedule_once(msg.0, Ring("once")));
+        Ok(())
+    }
+}
+
+struct Repeat(Duration);
+impl Receives<Repeat> for Alarm {
+    type Response = ();
+    fn receive(&mut self, msg: Repeat, ctx: &Context<Self>) -> Result<(), Failure> {
+        self.timer = Some(ctx.schedule_repeating(msg.0, Ring("repeat")));
+        Ok(())
+    }
+}
+
+struct Cancel;
+impl Receives<Cancel> for Alarm {
+    type Response = ();
+    fn receive(&mut self, _: Cancel, _: &Context<Self>) -> Result<(), Failure> {
+        if let Some(timer) = self.timer.take() {
+            timer.cancel();
+        }
+        Ok(())
+    }
+}
+
+struct Fail;
+impl Receives<Fail> for Alarm {
+    type Response = ();
+    fn receive(&mut self, _: Fail, _: &Context<Self>) -> Result<(), Failure> {
+        Err("broken".into())
+    }
+}
+
+fn alarm(system: &mut System, log: &Log) -> Address<Alarm> {
+    system.new_actor(AlarmProps(log.clone()))
+}
+
+/// Let some time pass, then handle whatever came due
+fn wait_ticks(system: &mut System, ticks: u32) {
+    thread::sleep(TICK * ticks);
+    system.run_until_idle();
+}
+
+#[test]
+fn a_one_off_timer_fires_once_after_its_delay() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let alarm = alarm(&mut system, &log);
+
+    alarm.send(Once(TICK * 3));
+    system.run_until_idle();
+    assert!(events(&log).is_empty());
+
+    wait_ticks(&mut system, 4);
+    wait_ticks(&mut system, 4);
+    assert_eq!(events(&log), vec!["once"]);
+}
+
+#[test]
+fn a_repeating_timer_fires_until_cancelled() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let alarm = alarm(&mut system, &log);
+
+    alarm.send(Repeat(TICK));
+    system.run_until_idle();
+    for _ in 0..3 {
+        wait_ticks(&mut system, 2);
+    }
+    let fired = events(&log).len();
+    assert!(fired >= 3, "fired {} times", fired);
+
+    alarm.send(Cancel);
+    system.run_until_idle();
+    let fired = events(&log).len();
+    wait_ticks(&mut system, 3);
+    assert_eq!(events(&log).len(), fired);
+}
+
+#[test]
+fn a_cancelled_timer_never_fires() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let alarm = alarm(&mut system, &log);
+
+    alarm.send(Once(TICK));
+    alarm.send(Cancel);
+    system.run_until_idle();
+    wait_ticks(&mut system, 3);
+    assert!(events(&log).is_empty());
+}
+
+#[test]
+fn timers_are_cancelled_when_the_actor_restarts() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let alarm = alarm(&mut system, &log);
+
+    alarm.send(Repeat(TICK));
+    alarm.send(Fail);
+    system.run_until_idle();
+    wait_ticks(&mut system, 3);
+    assert!(events(&log).is_empty());
+}
+
+#[test]
+fn repeating_with_a_zero_interval_panics() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let alarm = alarm(&mut system, &log);
+
+    let reply = alarm.ask(Repeat(Duration::from_secs(0)));
+    system.run_until_idle();
+    match reply.poll() {
+        Err(AskError::Failed(reason)) => assert!(reason.contains("interval of more than 0"), "{}", reason),
+        other => panic!("expected the handler to panic, got {:?}", other),
+    }
+}
@@ -0,0 +1,149 @@
+//! Death watch: `Context::watch`, `Context::unwatch` and `Terminated` notifications.
+extern crate romeo;
+
+mod common;
+
+use common::{events, manual_system, new_log, record, Log};
+use romeo::actor::*;
+use romeo::mailbox::Overflow;
+use romeo::system::Config;
+use romeo::{Address, System};
+
+struct Node {
+    name: &'static str,
+    log: Log,
+}
+impl Actor for Node {}
+
+struct NodeProps {
+    name: &'static str,
+    log: Log,
+}
+impl Props for NodeProps {}
+impl ActorConstructable<NodeProps> for Node {
+    fn new(props: &NodeProps) -> Self {
+        Node {
+            name: props.name,
+            log: props.log.clone(),
+        }
+    }
+}
+
+struct Watch(Address<Node>);
+impl Receives<Watch> for Node {
+    type Response = ();
+    fn receive(&mut self, msg: Watch, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.watch(&msg.0);
+        Ok(())
+    }
+}
+
+struct Unwatch(Address<Node>);
+impl Receives<Unwatch> for Node {
+    type Response = ();
+    fn receive(&mut self, msg: Unwatch, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.unwatch(&msg.0);
+        Ok(())
+    }
+}
+
+struct Stop;
+impl Receives<Stop> for Node {
+    type Response = ();
+    fn receive(&mut self, _: Stop, ctx: &Context<Self>) -> Result<(), Failure> {
+        ctx.stop();
+        Ok(())
+    }
+}
+
+struct Noop;
+impl Receives<Noop> for Node {
+    type Response = ();
+    fn receive(&mut self, _: Noop, _: &Context<Self>) -> Result<(), Failure> {
+        record(&self.log, format!("{} noop", self.name));
+        Ok(())
+    }
+}
+
+impl Receives<Terminated> for Node {
+    type Response = ();
+    fn receive(&mut self, _: Terminated, _: &Context<Self>) -> Result<(), Failure> {
+        record(&self.log, format!("{} saw terminated", self.name));
+        Ok(())
+    }
+}
+
+fn node(system: &mut System, name: &'static str, log: &Log) -> Address<Node> {
+    node_with(system, name, log, ActorConfig::default())
+}
+
+fn node_with(system: &mut System, name: &'static str, log: &Log, config: ActorConfig) -> Address<Node> {
+    system.new_actor_with(NodeProps { name, log: log.clone() }, config)
+}
+
+#[test]
+fn watching_a_stopped_actor_notifies_right_away() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let watcher = node(&mut system, "watcher", &log);
+    let watched = node(&mut system, "watched", &log);
+    watched.send(Stop);
+    system.run_until_idle();
+
+    watcher.send(Watch(watched));
+    system.run_until_idle();
+    assert_eq!(events(&log), vec!["watcher saw terminated".to_string()]);
+}
+
+#[test]
+fn no_notification_is_handled_after_unwatching() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let watcher = node(&mut system, "watcher", &log);
+    let watched = node(&mut system, "watched", &log);
+    watcher.send(Watch(watched.clone()));
+    system.run_until_idle();
+
+    // the watched actor stops before it gets to the unwatch, notifying the watcher regardless
+    watched.send(Stop);
+    watcher.send(Unwatch(watched.clone()));
+    system.run_until_idle();
+    assert!(events(&log).is_empty());
+}
+
+#[test]
+fn each_watch_is_notified_once() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let watcher = node(&mut system, "watcher", &log);
+    let first = node(&mut system, "first", &log);
+    let second = node(&mut system, "second", &log);
+    watcher.send(Watch(first.clone()));
+    watcher.send(Watch(second.clone()));
+    system.run_until_idle();
+
+    first.send(Stop);
+    second.send(Stop);
+    system.run_until_idle();
+    assert_eq!(events(&log), vec!["watcher saw terminated".to_string(); 2]);
+}
+
+#[test]
+fn a_full_blocking_mailbox_does_not_hold_up_the_stopped_actor() {
+    let mut system = manual_system(Config::default());
+    let log = new_log();
+    let dead_letters = system.dead_letters();
+    let watcher = node_with(&mut system, "watcher", &log, ActorConfig::default().bounded_mailbox(1, Overflow::Block));
+    let watched = node(&mut system, "watched", &log);
+    watcher.send(Watch(watched.clone()));
+    system.run_until_idle();
+
+    // the watched actor handles the stop, and then stops while the watcher's mailbox is full
+    watched.send(Stop);
+    assert!(system.step());
+    watcher.send(Noop);
+    system.run_until_idle();
+
+    assert_eq!(events(&log), vec!["watcher noop".to_string()]);
+    assert_eq!(dead_letters.count(), 1);
+}
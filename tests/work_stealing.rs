@@ -0,0 +1,106 @@
+//! Idle schedulers stealing runnable actors from busy ones.
+extern crate romeo;
+
+mod common;
+
+use common::{events, new_log, spawned_system, Log};
+use romeo::actor::*;
+use romeo::placement::{Placement, PlacementRequest};
+use romeo::system::Config;
+use romeo::Address;
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Places every actor on the first scheduler, leaving the others idle unless they steal
+struct FirstScheduler;
+impl Placement for FirstScheduler {
+    fn place(&self, _: &PlacementRequest) -> usize {
+        0
+    }
+}
+
+struct Sleeper {
+    log: Log,
+    busy: AtomicBool,
+}
+impl Actor for Sleeper {}
+
+struct SleeperProps(Log);
+impl Props for SleeperProps {}
+impl ActorConstructable<SleeperProps> for Sleeper {
+    fn new(props: &SleeperProps) -> Self {
+        Sleeper {
+            log: props.0.clone(),
+            busy: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Sleep for a while, recording the thread the actor ran on
+struct Nap;
+impl Receives<Nap> for Sleeper {
+    type Response = ();
+    fn receive(&mut self, _: Nap, _: &Context<Self>) -> Result<(), Failure> {
+        if self.busy.swap(true, Ordering::SeqCst) {
+            common::record(&self.log, "ran on two threads at once".to_string());
+        }
+        thread::sleep(Duration::from_millis(5));
+        let name = thread::current().name().unwrap_or_default().to_string();
+        common::record(&self.log, name);
+        self.busy.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Record the number, after a nap
+struct Count(u32);
+impl Receives<Count> for Sleeper {
+    type Response = ();
+    fn receive(&mut self, msg: Count, _: &Context<Self>) -> Result<(), Failure> {
+        thread::sleep(Duration::from_millis(1));
+        common::record(&self.log, format!("count {}", msg.0));
+        Ok(())
+    }
+}
+
+#[test]
+fn idle_schedulers_steal_from_a_busy_one() {
+    let mut system = spawned_system(Config::default().threads(2).placement(FirstScheduler));
+    let log = new_log();
+    let sleepers: Vec<Address<Sleeper>> = (0..4).map(|_| system.new_actor(SleeperProps(log.clone()))).collect();
+
+    for _ in 0..10 {
+        for sleeper in &sleepers {
+            sleeper.send(Nap);
+        }
+    }
+    assert!(common::eventually(|| events(&log).len() == 40));
+
+    let threads: HashSet<String> = events(&log).into_iter().collect();
+    assert!(!threads.contains("ran on two threads at once"));
+    assert_eq!(threads.len(), 2, "both schedulers should have run actors, ran on {:?}", threads);
+    assert!(system.graceful_shutdown().is_ok());
+}
+
+#[test]
+fn a_stolen_actor_still_handles_its_messages_in_order() {
+    let mut system = spawned_system(Config::default().threads(4).placement(FirstScheduler));
+    let log = new_log();
+    let sleepers: Vec<Address<Sleeper>> = (0..8).map(|_| system.new_actor(SleeperProps(new_log()))).collect();
+    let ordered: Address<Sleeper> = system.new_actor(SleeperProps(log.clone()));
+
+    for n in 0..20 {
+        for sleeper in &sleepers {
+            sleeper.send(Nap);
+        }
+        ordered.send(Count(n));
+    }
+    assert!(common::eventually(|| events(&log).len() == 20));
+
+    let expected: Vec<String> = (0..20).map(|n| format!("count {}", n)).collect();
+    assert_eq!(events(&log), expected);
+    assert!(system.graceful_shutdown().is_ok());
+}